@@ -35,9 +35,25 @@ fn main() {
         write_file("openclonk/CMakeLists.txt", &cmakelists_patched).unwrap();
     }
 
+    let target = env::var("TARGET").unwrap();
+    let emscripten = target.contains("emscripten");
+
     // Build libmisc and libc4script via cmake.
     let mut cmake_cfg = cmake::Config::new("openclonk");
     cmake_cfg.define("HEADLESS_ONLY", "ON");
+    if emscripten {
+        // Default to the toolchain file shipped with emsdk.
+        if env::var("CMAKE_TOOLCHAIN_FILE").is_err() {
+            if let Ok(emsdk) = env::var("EMSDK") {
+                cmake_cfg.define("CMAKE_TOOLCHAIN_FILE",
+                                 format!("{}/upstream/emscripten/cmake/Modules/Platform/Emscripten.cmake", emsdk));
+            }
+        }
+        // zlib comes as emscripten port. Exceptions are required for error reporting in the
+        // glue code, but disabled by default.
+        cmake_cfg.cflag("-sUSE_ZLIB=1")
+                 .cxxflag("-sUSE_ZLIB=1 -fexceptions");
+    }
     let cmake_dst = cmake_cfg.build_target("libmisc").build();
     cmake_cfg.build_target("libc4script").build();
     cmake_cfg.build_target("blake2").build();
@@ -55,6 +71,10 @@ fn main() {
     if env::var("PROFILE").unwrap() == "debug" {
         cfg.define("_DEBUG", Some("1"));
     }
+    if emscripten {
+        cfg.flag("-sUSE_ZLIB=1")
+           .flag("-fexceptions");
+    }
 
     // Find file list from cmake.
     let cmake_vars = get_cmake_vars(&cmakelists);
@@ -77,9 +97,12 @@ fn main() {
     println!("cargo:rustc-link-lib=static=libc4script");
     println!("cargo:rustc-link-lib=static=libmisc");
     println!("cargo:rustc-link-lib=static=blake2");
-    println!("cargo:rustc-link-lib=z");
+    // With emscripten, the final executable has to link zlib with -sUSE_ZLIB=1 instead.
+    if !emscripten {
+        println!("cargo:rustc-link-lib=z");
+    }
 
-    if env::var("TARGET").unwrap().contains("windows") {
+    if target.contains("windows") {
         println!("cargo:rustc-link-lib=winmm");
     }
 
@@ -0,0 +1,44 @@
+//! Writes a rendered map into a scenario.
+
+use crate::{Result, ResultExt, Setup, render_args, is_scenario};
+
+use clap::{Arg, ArgMatches, App, SubCommand};
+use error_chain::bail;
+use ocmapgen::Group;
+
+pub fn subcommand<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("export")
+        .about("Renders a map and writes it into a scenario as Map.bmp and MapBg.bmp")
+        .args(&render_args())
+        .arg(Arg::with_name("static")
+             .long("static")
+             .help("Make the scenario always use this map: removes Map.c and fixes the map size in Scenario.txt")
+             .takes_value(false))
+        .arg(Arg::with_name("INPUT")
+             .help("Input file (e.g. Map.c) or scenario (.ocs)")
+             .required(true)
+             .index(1))
+        .arg(Arg::with_name("SCENARIO")
+             .help("Scenario to write the map into (defaults to the input scenario)")
+             .index(2))
+}
+
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    let setup = Setup::new(matches)?;
+    let scenario_file = match matches.value_of("SCENARIO") {
+        Some(f) => f.to_owned(),
+        None if is_scenario(&setup.input_file) => setup.input_file.to_str().unwrap().to_owned(),
+        None => bail!("no scenario given to write the map into"),
+    };
+
+    let map_handle = setup.build(matches)?.render().chain_err(|| "map rendering failed")?;
+    if let Some(warnings) = map_handle.warnings() {
+        eprintln!("{}", warnings);
+    }
+    let mut group = Group::open(&scenario_file, false)
+        .chain_err(|| format!("couldn't open {}", scenario_file))?;
+    map_handle.export_to_scenario(&mut group, matches.is_present("static"))
+        .chain_err(|| format!("couldn't write map into {}", scenario_file))?;
+    println!("Wrote {}x{} map with seed {} into {}", map_handle.width(), map_handle.height(), map_handle.seed(), scenario_file);
+    Ok(())
+}
@@ -0,0 +1,74 @@
+//! Checks rendered maps against stored expected outputs.
+
+use crate::{Result, ResultExt};
+
+use clap::{Arg, ArgMatches, App, SubCommand};
+use error_chain::bail;
+use ocmapgen::easy::Easy;
+use ocmapgen::golden::{GoldenResult, GoldenSuite};
+use ocmapgen::AssetConfig;
+
+use std::path::Path;
+
+pub fn subcommand<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("test")
+        .about("Renders the maps listed in a test directory and compares them with the expected outputs")
+        .arg(Arg::with_name("root")
+             .short("r").long("root")
+             .help("Base directory. Should be a subdirectory of the OpenClonk “planet” root directory (defaults to the test directory)")
+             .takes_value(true))
+        .arg(Arg::with_name("definitions")
+             .short("d").long("definitions")
+             .help("Load additional definitions (.ocd) for use by Map.c")
+             .takes_value(true)
+             .multiple(true)
+             .number_of_values(1))
+        .arg(Arg::with_name("update")
+             .long("update")
+             .help("Overwrite the expected outputs with the rendered maps")
+             .takes_value(false))
+        .arg(Arg::with_name("DIR")
+             .help("Test directory with a Golden.txt manifest")
+             .required(true)
+             .index(1))
+}
+
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    let dir = Path::new(matches.value_of("DIR").unwrap())
+        .canonicalize()
+        .chain_err(|| "couldn't resolve test directory path")?;
+    let suite = GoldenSuite::load(&dir).chain_err(|| "couldn't load tests")?;
+
+    let mut mapgen = Easy::new().chain_err(|| "couldn't initialize map generator")?;
+    let mut asset_config = AssetConfig::discover(matches.value_of("root").map(Path::new).unwrap_or(&dir))
+        .chain_err(|| "couldn't find Material.ocg or Objects.ocd")?;
+    for definition in matches.values_of("definitions").into_iter().flatten() {
+        asset_config.definition(definition);
+    }
+    mapgen.load_assets(&asset_config)
+        .chain_err(|| "couldn't load materials or scripts")?;
+
+    let results = suite.run(&mapgen, matches.is_present("update"));
+    for (case, result) in &results {
+        match result {
+            GoldenResult::Passed => println!("ok       {}", case.name),
+            GoldenResult::Updated => println!("updated  {}", case.name),
+            GoldenResult::Missing => println!("MISSING  {} (run with --update)", case.name),
+            GoldenResult::Mismatch(m) if m.expected_size != m.actual_size => {
+                println!("FAILED   {}: size {}x{}, expected {}x{}", case.name,
+                         m.actual_size.0, m.actual_size.1, m.expected_size.0, m.expected_size.1);
+            },
+            GoldenResult::Mismatch(m) => {
+                println!("FAILED   {}: {} foreground and {} background pixels differ", case.name, m.fg_pixels, m.bg_pixels);
+            },
+            GoldenResult::Failed(e) => {
+                println!("FAILED   {}: {}", case.name, e.iter().map(|e| e.to_string()).collect::<Vec<_>>().join(": "));
+            },
+        }
+    }
+    let failed = results.iter().filter(|(_, result)| !result.is_ok()).count();
+    if failed > 0 {
+        bail!("{} of {} maps failed", failed, results.len());
+    }
+    Ok(())
+}
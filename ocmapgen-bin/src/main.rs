@@ -1,9 +1,10 @@
-use clap::{Arg, App, value_t};
-use error_chain::{error_chain, quick_main};
+use clap::{Arg, App, AppSettings, ArgMatches, value_t};
+use error_chain::{bail, error_chain, quick_main};
 use notify::{Watcher, RecursiveMode, DebouncedEvent, watcher};
 use ocmapgen::easy::{Easy, RenderConfig, MapType, load_scenpar};
-use ocmapgen::{openclonk_version, seed_rng};
+use ocmapgen::{openclonk_version, seed_rng, AnimationFrame, AssetConfig, MapGenHandle, Scenario, Scenpar};
 use ocmapgen_bin::msg;
+use serde::ser::{Serialize, SerializeMap, Serializer};
 
 use std::path::{Path, PathBuf};
 use std::sync::mpsc::channel;
@@ -12,38 +13,17 @@ use std::io::prelude::*;
 
 error_chain! { }
 
+mod export;
+mod golden;
+mod matrix;
+mod sweep;
+
 quick_main!(run);
 fn run() -> Result<()> {
     let matches = App::new("ocmapgen")
         .version(format!("{} with OC {}", env!("CARGO_PKG_VERSION"), openclonk_version()).as_str())
-        .arg(Arg::with_name("root")
-             .short("r").long("root")
-             .help("Base directory. Should be a subdirectory of the OpenClonk “planet” root directory (defaults to directory of input file)")
-             .takes_value(true))
-        .arg(Arg::with_name("seed")
-             .short("s").long("seed")
-             .help("Set a fixed RNG seed value (defaults to a random seed)")
-             .takes_value(true))
-        .arg(Arg::with_name("width")
-             .short("w").long("width")
-             .help("Width of the output image")
-             .takes_value(true)
-             .default_value("200"))
-        .arg(Arg::with_name("height")
-             .short("h").long("height")
-             .help("Height of the output image")
-             .takes_value(true)
-             .default_value("200"))
-        .arg(Arg::with_name("players")
-             .long("players")
-             .help("Set the result of GetStartupPlayerCount()")
-             .takes_value(true)
-             .default_value("1"))
-        .arg(Arg::with_name("teams")
-             .long("teams")
-             .help("Set the result of GetStartupTeamCount()")
-             .takes_value(true)
-             .default_value("1"))
+        .setting(AppSettings::SubcommandsNegateReqs)
+        .args(&render_args())
         .arg(Arg::with_name("watch")
              .long("watch")
              .help("Watch input file for changes")
@@ -52,117 +32,422 @@ fn run() -> Result<()> {
              .long("bg")
              .help("Write map background to file")
              .takes_value(true))
-        .arg(Arg::with_name("map-type")
-             .long("map-type")
-             .help("Type of map. Inferred from input file name per default")
+        .arg(Arg::with_name("animated-textures")
+             .long("animated-textures")
+             .help("How to draw animated textures like lava: with their first frame, the average color of all frames, or as animated GIF cycling through the frames (output must be .gif)")
              .takes_value(true)
-             .possible_values(&["Landscape.txt", "Map.c"]))
+             .possible_values(&["first", "average", "animate"])
+             .default_value("first"))
+        .arg(Arg::with_name("list-scenpars")
+             .long("list-scenpars")
+             .help("List the scenario parameters defined in ParameterDefs.txt and exit")
+             .takes_value(false))
+        .arg(Arg::with_name("list-materials")
+             .long("list-materials")
+             .help("List the loaded materials and texture map entries usable in Map.c and exit")
+             .takes_value(false))
         .arg(Arg::with_name("cbor")
              .long("cbor")
              .help("Enable cbor interface")
              .hidden(true) // not useful for human users
              .takes_value(false))
         .arg(Arg::with_name("INPUT")
-             .help("Input file (e.g. Map.c)")
+             .help("Input file (e.g. Map.c) or scenario (.ocs)")
              .required_unless_all(&["cbor", "root", "map-type"])
              .index(1))
         .arg(Arg::with_name("OUTPUT")
              .help("Output file (e.g. Map.png)")
-             .required_unless("cbor")
+             .required_unless_one(&["cbor", "list-scenpars", "list-materials"])
              .index(2))
+        .subcommand(matrix::subcommand())
+        .subcommand(sweep::subcommand())
+        .subcommand(golden::subcommand())
+        .subcommand(export::subcommand())
         .get_matches();
 
-    let mut mapgen = Easy::new().chain_err(|| "couldn't initialize map generator")?;
-    let input_file = match matches.value_of("INPUT") {
-        Some(f) => Path::new(f)
-                   .canonicalize()
-                   .chain_err(|| "couldn't resolve input file path")?,
-        None => PathBuf::new() // dummy, won't be used
-    };
-    let output_file = matches.value_of("OUTPUT").unwrap_or("");
-    let base_path = match matches.value_of("root") {
-        Some(p) => p.to_owned(),
-        None => {
-            let mut p = input_file.clone();
-            p.pop();
-            p.to_str().unwrap().to_owned()
-        }
-    };
-    mapgen.set_base_path(&base_path)
-        .chain_err(|| "couldn't find Material.ocg or Objects.ocd")?;
-
-    let seed = value_t!(matches.value_of("seed"), u32)
-               .unwrap_or_else(|_| SystemTime::now().duration_since(UNIX_EPOCH)
-                                   .expect("failed getting a timestamp")
-                                   .subsec_nanos());
-    seed_rng(seed);
-
-    let players = value_t!(matches.value_of("players"), i32)
-                  .chain_err(|| "invalid --players option")?;
-    let teams = value_t!(matches.value_of("teams"), i32)
-                  .chain_err(|| "invalid --teams option")?;
-    mapgen.mapgen.set_startup_player_count(players);
-    mapgen.mapgen.set_startup_team_count(teams);
-
-    let width = value_t!(matches.value_of("width"), u32)
-                .chain_err(|| "invalid width")?;
-    let height = value_t!(matches.value_of("height"), u32)
-                .chain_err(|| "invalid height")?;
-    let maybe_scenpar = load_scenpar(&base_path);
-    let mut cfg = mapgen.build();
-    if let Ok(ref scenpar) = maybe_scenpar {
-        cfg.scenpar(scenpar);
+    match matches.subcommand() {
+        ("matrix", Some(matches)) => return matrix::run(matches),
+        ("sweep", Some(matches)) => return sweep::run(matches),
+        ("test", Some(matches)) => return golden::run(matches),
+        ("export", Some(matches)) => return export::run(matches),
+        _ => (),
     }
 
-    cfg.width(width)
-       .height(height);
+    let setup = Setup::new(&matches)?;
+    let input_file = &setup.input_file;
+    let output_file = matches.value_of("OUTPUT").unwrap_or("");
 
-    if matches.is_present("INPUT") {
-        cfg.filename(input_file.to_str().unwrap());
+    if matches.is_present("list-scenpars") {
+        list_scenpars(setup.scenpar());
+        return Ok(());
     }
 
-    match matches.value_of("map-type") {
-        Some("Landscape.txt") => { cfg.map_type(MapType::LandscapeTxt); },
-        Some("Map.c")         => { cfg.map_type(MapType::MapC); },
-        _ => () // clap filters invalid values
+    if matches.is_present("list-materials") {
+        list_materials(&setup.mapgen);
+        return Ok(());
     }
 
     let bg_output = matches.value_of("bg-output");
+    let animation = match matches.value_of("animated-textures") {
+        Some("average") => Animation::Average,
+        Some("animate") => Animation::Animate,
+        _ => Animation::First,
+    };
+    if animation == Animation::Animate {
+        for file in Some(output_file).into_iter().chain(bg_output) {
+            if !is_gif(file) {
+                bail!("--animated-textures animate needs .gif output files, got {}", file);
+            }
+        }
+    }
 
-    if matches.is_present("cbor") {
-        handle_requests(cfg, bg_output, matches.value_of("seed").map(|_| seed))?;
+    if setup.scenario.is_some() {
+        render(&setup.build(&matches)?, output_file, bg_output, animation)?;
+
+        if matches.is_present("watch") {
+            // Reload the scenario on changes to any of its files.
+            watch(input_file, RecursiveMode::Recursive, |_| true, || {
+                let scenario = load_scenario(input_file, &setup.scenpar_values)?;
+                let mut cfg = setup.mapgen.build_scenario(&scenario);
+                if let Some(seed) = setup.seed {
+                    cfg.seed(seed);
+                }
+                output(cfg.render(), output_file, bg_output, animation)
+            })?;
+        }
+    } else if matches.is_present("cbor") {
+        handle_requests(setup.build(&matches)?, bg_output)?;
     } else {
-        render(&cfg, output_file, bg_output)?;
+        let cfg = setup.build(&matches)?;
+        render(&cfg, output_file, bg_output, animation)?;
 
         if matches.is_present("watch") {
-            watch(&cfg, &input_file, output_file, bg_output, matches.value_of("seed").map(|_| seed))?;
+            // Watch the parent directory as the file may be removed temporarily on write.
+            let dir = input_file.parent().unwrap();
+            watch(dir, RecursiveMode::NonRecursive, |f| f == input_file, || {
+                render(&cfg, output_file, bg_output, animation)
+            })?;
         }
     }
 
     Ok(())
 }
 
-fn render(cfg: &RenderConfig, output_file: &str, output_file_bg: Option<&str>) -> Result<()> {
-    let map_handle = cfg.render().chain_err(|| "map rendering failed")?;
+/// Arguments for setting up and configuring the map generator, shared by all commands.
+fn render_args<'a, 'b>() -> Vec<Arg<'a, 'b>> {
+    vec![
+        Arg::with_name("root")
+             .short("r").long("root")
+             .help("Base directory. Should be a subdirectory of the OpenClonk “planet” root directory (defaults to directory of input file)")
+             .takes_value(true),
+        Arg::with_name("seed")
+             .short("s").long("seed")
+             .help("Set a fixed RNG seed value (defaults to a random seed)")
+             .takes_value(true),
+        Arg::with_name("width")
+             .short("w").long("width")
+             .help("Width of the output image. Use “standard,random,min,max” for a random width like MapWidth in Scenario.txt")
+             .takes_value(true)
+             .default_value("200"),
+        Arg::with_name("height")
+             .short("h").long("height")
+             .help("Height of the output image. Use “standard,random,min,max” for a random height like MapHeight in Scenario.txt")
+             .takes_value(true)
+             .default_value("200"),
+        Arg::with_name("players")
+             .long("players")
+             .help("Set the result of GetStartupPlayerCount()")
+             .takes_value(true)
+             .default_value("1"),
+        Arg::with_name("player-extend")
+             .long("player-extend")
+             .help("Grow the map width with the number of players, like MapPlayerExtend in Scenario.txt")
+             .takes_value(false),
+        Arg::with_name("teams")
+             .long("teams")
+             .help("Set the result of GetStartupTeamCount()")
+             .takes_value(true)
+             .default_value("1"),
+        Arg::with_name("map-type")
+             .long("map-type")
+             .help("Type of map. Inferred from input file name per default")
+             .takes_value(true)
+             .possible_values(&["Landscape.txt", "Map.c"]),
+        Arg::with_name("definitions")
+             .short("d").long("definitions")
+             .help("Load additional definitions (.ocd) for use by Map.c")
+             .takes_value(true)
+             .multiple(true)
+             .number_of_values(1),
+        Arg::with_name("scenpar")
+             .long("scenpar")
+             .help("Set a scenario parameter from ParameterDefs.txt, e.g. --scenpar Difficulty=2")
+             .takes_value(true)
+             .multiple(true)
+             .number_of_values(1)
+             .value_name("ID=VALUE"),
+        Arg::with_name("show-assets")
+             .long("show-assets")
+             .help("Print which materials and scripts were loaded from where")
+             .takes_value(false),
+    ]
+}
+
+/// The map generator with assets loaded and the input to render, as given by `render_args`.
+struct Setup {
+    mapgen: Easy,
+    input_file: PathBuf,
+    /// Fixed seed given with --seed.
+    seed: Option<u32>,
+    scenario: Option<Scenario>,
+    /// Parameters from ParameterDefs.txt next to a single map file.
+    scenpar: Option<Scenpar>,
+    scenpar_values: Vec<(String, i32)>,
+}
+
+impl Setup {
+    fn new(matches: &ArgMatches) -> Result<Setup> {
+        let mut mapgen = Easy::new().chain_err(|| "couldn't initialize map generator")?;
+        let input_file = match matches.value_of("INPUT") {
+            Some(f) => Path::new(f)
+                       .canonicalize()
+                       .chain_err(|| "couldn't resolve input file path")?,
+            None => PathBuf::new() // dummy, won't be used
+        };
+        let scenario_mode = is_scenario(&input_file);
+        let base_path = match matches.value_of("root") {
+            Some(p) => p.to_owned(),
+            None if scenario_mode => input_file.to_str().unwrap().to_owned(),
+            None => {
+                let mut p = input_file.clone();
+                p.pop();
+                p.to_str().unwrap().to_owned()
+            }
+        };
+        let mut asset_config = AssetConfig::discover(&base_path)
+            .chain_err(|| "couldn't find Material.ocg or Objects.ocd")?;
+        for definition in matches.values_of("definitions").into_iter().flatten() {
+            asset_config.definition(definition);
+        }
+        let asset_report = mapgen.load_assets(&asset_config)
+            .chain_err(|| "couldn't load materials or scripts")?;
+        if matches.is_present("show-assets") {
+            print!("{}", asset_report);
+        }
+
+        let seed = match matches.value_of("seed") {
+            Some(_) => Some(value_t!(matches.value_of("seed"), u32).chain_err(|| "invalid --seed option")?),
+            None => {
+                // Renders draw their seeds from the global RNG.
+                seed_rng(random_seed());
+                None
+            },
+        };
+
+        let players = value_t!(matches.value_of("players"), i32)
+                      .chain_err(|| "invalid --players option")?;
+        let teams = value_t!(matches.value_of("teams"), i32)
+                      .chain_err(|| "invalid --teams option")?;
+        mapgen.mapgen.set_startup_player_count(players);
+        mapgen.mapgen.set_startup_team_count(teams);
+
+        let scenpar_values = matches.values_of("scenpar").into_iter().flatten()
+            .map(parse_scenpar_arg)
+            .collect::<Result<Vec<_>>>()?;
+        let scenario = if scenario_mode {
+            Some(load_scenario(&input_file, &scenpar_values)?)
+        } else {
+            None
+        };
+        let mut scenpar = if scenario_mode { None } else { load_scenpar(&base_path).ok() };
+        set_scenpars(scenpar.as_mut(), &scenpar_values)?;
+
+        Ok(Setup { mapgen, input_file, seed, scenario, scenpar, scenpar_values })
+    }
+
+    /// Returns the parameters of the scenario or of the map file.
+    fn scenpar(&self) -> Option<&Scenpar> {
+        match self.scenario {
+            Some(ref scenario) => scenario.scenpar(),
+            None => self.scenpar.as_ref(),
+        }
+    }
+
+    fn scenpar_mut(&mut self) -> Option<&mut Scenpar> {
+        match self.scenario {
+            Some(ref mut scenario) => scenario.scenpar_mut(),
+            None => self.scenpar.as_mut(),
+        }
+    }
+
+    /// Builds the render configuration for the scenario or map file.
+    ///
+    /// Scenarios take the map size from their Scenario.txt, so the size options are rejected
+    /// for them.
+    fn build(&self, matches: &ArgMatches) -> Result<RenderConfig<'_>> {
+        let mut cfg = match self.scenario {
+            Some(ref scenario) => self.mapgen.build_scenario(scenario),
+            None => self.mapgen.build(),
+        };
+        if let Some(seed) = self.seed {
+            cfg.seed(seed);
+        }
+        if self.scenario.is_some() {
+            for arg in &["width", "height", "player-extend"] {
+                if matches.occurrences_of(arg) > 0 {
+                    bail!("--{} can't be used with scenarios, which take the map size from Scenario.txt", arg);
+                }
+            }
+            return Ok(cfg);
+        }
+        if let Some(ref scenpar) = self.scenpar {
+            cfg.scenpar(scenpar);
+        }
+
+        cfg.map_player_extend(matches.is_present("player-extend"));
+        let width = matches.value_of("width").unwrap();
+        let height = matches.value_of("height").unwrap();
+        if width.contains(',') {
+            cfg.map_width(width.parse().chain_err(|| "invalid width")?);
+        } else {
+            cfg.width(width.parse().chain_err(|| "invalid width")?);
+        }
+        if height.contains(',') {
+            cfg.map_height(height.parse().chain_err(|| "invalid height")?);
+        } else {
+            cfg.height(height.parse().chain_err(|| "invalid height")?);
+        }
+
+        if matches.is_present("INPUT") {
+            cfg.filename(self.input_file.to_str().unwrap());
+        }
+
+        match matches.value_of("map-type") {
+            Some("Landscape.txt") => { cfg.map_type(MapType::LandscapeTxt); },
+            Some("Map.c")         => { cfg.map_type(MapType::MapC); },
+            _ => () // clap filters invalid values
+        }
+        Ok(cfg)
+    }
+}
+
+fn random_seed() -> u32 {
+    SystemTime::now().duration_since(UNIX_EPOCH)
+        .expect("failed getting a timestamp")
+        .subsec_nanos()
+}
+
+/// Checks whether the input is a scenario instead of a single map file.
+fn is_scenario(path: &Path) -> bool {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) => ext.eq_ignore_ascii_case("ocs"),
+        None => false,
+    }
+}
+
+fn load_scenario(path: &Path, scenpar_values: &[(String, i32)]) -> Result<Scenario> {
+    let mut scenario = Scenario::load(path).chain_err(|| "couldn't load scenario")?;
+    set_scenpars(scenario.scenpar_mut(), scenpar_values)?;
+    Ok(scenario)
+}
+
+/// Parses an ID=VALUE scenario parameter argument.
+fn parse_scenpar_arg(arg: &str) -> Result<(String, i32)> {
+    let mut parts = arg.splitn(2, '=');
+    match (parts.next(), parts.next()) {
+        (Some(id), Some(value)) if !id.is_empty() => {
+            let value = value.parse().chain_err(|| format!("invalid --scenpar value '{}'", value))?;
+            Ok((id.to_owned(), value))
+        },
+        _ => bail!("invalid --scenpar '{}', expected ID=VALUE", arg),
+    }
+}
+
+fn set_scenpars(scenpar: Option<&mut Scenpar>, values: &[(String, i32)]) -> Result<()> {
+    if values.is_empty() {
+        return Ok(());
+    }
+    let scenpar = match scenpar {
+        Some(s) => s,
+        None => bail!("--scenpar given, but there is no ParameterDefs.txt"),
+    };
+    for (id, value) in values {
+        scenpar.select(id, *value).chain_err(|| "invalid --scenpar")?;
+    }
+    Ok(())
+}
+
+fn list_scenpars(scenpar: Option<&Scenpar>) {
+    let defs = scenpar.map(|s| s.defs()).unwrap_or_default();
+    if defs.is_empty() {
+        println!("No scenario parameters defined.");
+    }
+    for def in defs {
+        let value = scenpar.unwrap().value(&def);
+        println!("{}: {}{}", def.id, def.name, if def.achievement { " (achievement)" } else { "" });
+        for option in &def.options {
+            let marker = if option.value == value { "*" } else { " " };
+            println!("  {} {:>3}  {}", marker, option.value, option.name);
+        }
+    }
+}
+
+fn list_materials(mapgen: &Easy) {
+    println!("Materials:");
+    for (_, material) in mapgen.material_map.materials() {
+        let properties = material.properties();
+        let mut traits = vec![if properties.is_solid() {
+            "solid"
+        } else if properties.is_liquid() {
+            "liquid"
+        } else {
+            "passable"
+        }];
+        if properties.dig_free { traits.push("diggable"); }
+        if properties.instable { traits.push("instable"); }
+        if properties.incendiary { traits.push("incendiary"); }
+        if properties.extinguisher { traits.push("extinguisher"); }
+        if properties.is_light_source() { traits.push("light source"); }
+        println!("  {:<20} {}", material.name(), traits.join(", "));
+    }
+    println!("Texture map entries:");
+    for entry in mapgen.texture_map.entries(&mapgen.material_map) {
+        println!("  {:>3}  {}{}", entry.index, entry.name(), if entry.background { "" } else { " (foreground only)" });
+    }
+}
+
+/// How to draw animated textures (--animated-textures).
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Animation {
+    First,
+    Average,
+    Animate,
+}
+
+/// Display time of each animation frame in animated GIFs.
+const ANIMATION_FRAME_DELAY_MS: u32 = 100;
+
+fn render(cfg: &RenderConfig, output_file: &str, output_file_bg: Option<&str>, animation: Animation) -> Result<()> {
+    output(cfg.render(), output_file, output_file_bg, animation)
+}
+
+fn output(map_handle: ocmapgen::Result<MapGenHandle>, output_file: &str, output_file_bg: Option<&str>, animation: Animation) -> Result<()> {
+    let map_handle = map_handle.chain_err(|| "map rendering failed")?;
+    // Only animations need the frame count, which scans the whole map.
+    let frames = if animation == Animation::Animate { map_handle.animation_frames() } else { 1 };
     // write foreground map...
     if is_bmp(output_file) {
-        map_handle.save_map(output_file)
-                  .chain_err(|| "writing output image failed")
+        map_handle.save_map(output_file).chain_err(|| "couldn't save map")
     } else {
-        map_handle.map_as_image()
-                  .save(output_file)
-                  .chain_err(|| "writing output image failed")
-    }?;
+        save_image(output_file, animation, |frame| map_handle.map_frame_as_image(frame), frames)
+    }.chain_err(|| "writing output image failed")?;
     // ...and optionally background map
     if let Some(output_file_bg) = output_file_bg {
         if is_bmp(output_file_bg) {
-            map_handle.save_map_bg(output_file_bg)
-                      .chain_err(|| "writing bg output image failed")
+            map_handle.save_map_bg(output_file_bg).chain_err(|| "couldn't save map")
         } else {
-            map_handle.map_bg_as_image()
-                      .save(output_file_bg)
-                      .chain_err(|| "writing bg output image failed")
-        }?;
+            save_image(output_file_bg, animation, |frame| map_handle.map_bg_frame_as_image(frame), frames)
+        }.chain_err(|| "writing bg output image failed")?;
     }
     if let Some(warnings) = map_handle.warnings() {
         println!("{}", warnings);
@@ -173,18 +458,66 @@ fn render(cfg: &RenderConfig, output_file: &str, output_file_bg: Option<&str>) -
     Ok(())
 }
 
+/// Key-value pairs serialized as JSON object, keeping their order.
+struct OrderedMap<K, V>(Vec<(K, V)>);
+
+impl<K: Serialize, V: Serialize> Serialize for OrderedMap<K, V> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (key, value) in &self.0 {
+            map.serialize_key(key)?;
+            map.serialize_value(value)?;
+        }
+        map.end()
+    }
+}
+
+/// Writes a value as pretty-printed JSON file.
+fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<()> {
+    let mut json = serde_json::to_string_pretty(value)
+        .chain_err(|| format!("couldn't serialize {}", path.display()))?;
+    json.push('\n');
+    std::fs::write(path, json)
+        .chain_err(|| format!("writing {} failed", path.display()))
+}
+
+/// Saves a map image, drawing animated textures as requested.
+fn save_image<F>(path: &str, animation: Animation, draw: F, frames: usize) -> Result<()>
+    where F: Fn(AnimationFrame) -> image::RgbImage
+{
+    use image::codecs::gif::{GifEncoder, Repeat};
+    use image::{Delay, DynamicImage, Frame};
+
+    match animation {
+        Animation::First => draw(AnimationFrame::Frame(0)).save(path).chain_err(|| "couldn't save image"),
+        Animation::Average => draw(AnimationFrame::Average).save(path).chain_err(|| "couldn't save image"),
+        Animation::Animate => {
+            let file = std::fs::File::create(path).chain_err(|| format!("couldn't create {}", path))?;
+            let mut encoder = GifEncoder::new(file);
+            encoder.set_repeat(Repeat::Infinite).chain_err(|| "GIF encoding failed")?;
+            encoder.encode_frames((0..frames).map(|n| {
+                let image = DynamicImage::ImageRgb8(draw(AnimationFrame::Frame(n))).into_rgba8();
+                Frame::from_parts(image, 0, 0, Delay::from_numer_denom_ms(ANIMATION_FRAME_DELAY_MS, 1))
+            })).chain_err(|| "GIF encoding failed")
+        },
+    }
+}
+
 fn is_bmp(path: &str) -> bool {
     path.ends_with(".bmp")
 }
 
-fn watch(cfg: &RenderConfig, input_file: &Path, output_file: &str, output_file_bg: Option<&str>, seed: Option<u32>) -> Result<()> {
+fn is_gif(path: &str) -> bool {
+    path.ends_with(".gif")
+}
+
+fn watch<F, R>(path: &Path, mode: RecursiveMode, filter: F, render: R) -> Result<()>
+        where F: Fn(&Path) -> bool, R: Fn() -> Result<()> {
     let (tx, rx) = channel();
     let mut watcher = watcher(tx, Duration::from_millis(100))
         .chain_err(|| "could not initialize watcher")?;
 
-    // Watch the parent directory as the file may be removed temporarily on write.
-    let dir = input_file.parent().unwrap();
-    watcher.watch(dir, RecursiveMode::NonRecursive)
+    watcher.watch(path, mode)
         .chain_err(|| "could not start watcher")?;
 
     println!("Waiting for file changes…");
@@ -193,15 +526,12 @@ fn watch(cfg: &RenderConfig, input_file: &Path, output_file: &str, output_file_b
         let event = rx.recv().chain_err(|| "watch error")?;
         let rerender = match event {
             DebouncedEvent::Create(f) | DebouncedEvent::Write(f)
-                => f == input_file,
+                => filter(&f),
             _   => false
         };
         if rerender {
             println!("File changed, rendering map…");
-            if let Some(seed) = seed {
-                seed_rng(seed);
-            }
-            report_error(render(cfg, output_file, output_file_bg.clone()));
+            report_error(render());
         }
     }
 }
@@ -216,7 +546,7 @@ fn report_error<T>(res: Result<T>) {
     }
 }
 
-fn handle_requests(mut cfg: RenderConfig, output_file_bg: Option<&str>, seed: Option<u32>) -> Result<()> {
+fn handle_requests(mut cfg: RenderConfig, output_file_bg: Option<&str>) -> Result<()> {
     loop {
         let req = msg::read_request().chain_err(|| "couldn't read request")?;
         let res = match req {
@@ -231,16 +561,13 @@ fn handle_requests(mut cfg: RenderConfig, output_file_bg: Option<&str>, seed: Op
                             },
                         warnings: map_handle.warnings(),
                         script_output: map_handle.script_output(),
+                        seed: map_handle.seed(),
                     },
                     Err(err) => msg::Response::Error(format!("{}", err)),
                 }
             },
         };
         msg::write_response(&res).chain_err(|| "couldn't write response")?;
-
-        if let Some(seed) = seed {
-            seed_rng(seed);
-        }
     }
 }
 
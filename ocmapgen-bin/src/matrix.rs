@@ -0,0 +1,254 @@
+//! Renders a map for every combination of scenario parameter values.
+
+use crate::{Result, ResultExt, Setup, OrderedMap, render_args, random_seed, write_json};
+
+use clap::{Arg, ArgMatches, App, SubCommand, value_t};
+use error_chain::bail;
+use ocmapgen::{ContactSheet, Scenpar};
+use serde_derive::Serialize;
+
+use std::path::Path;
+
+/// Default for --max-maps, enough for a few parameters with a handful of values each.
+const DEFAULT_MAX_MAPS: &str = "256";
+
+pub fn subcommand<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("matrix")
+        .about("Renders a map for every combination of scenario parameter values into a contact sheet")
+        .args(&render_args())
+        .arg(Arg::with_name("vary")
+             .long("vary")
+             .help("Parameter to vary, optionally with a subset of its values (defaults to all parameters except achievements)")
+             .takes_value(true)
+             .multiple(true)
+             .number_of_values(1)
+             .value_name("ID[=V1,V2,...]"))
+        .arg(Arg::with_name("columns")
+             .long("columns")
+             .help("Number of maps per row (defaults to about square)")
+             .takes_value(true))
+        .arg(Arg::with_name("scale")
+             .long("scale")
+             .help("Scale up each map by this factor")
+             .takes_value(true)
+             .default_value("1"))
+        .arg(Arg::with_name("max-maps")
+             .long("max-maps")
+             .help("Refuse to render more maps than this")
+             .takes_value(true)
+             .default_value(DEFAULT_MAX_MAPS))
+        .arg(Arg::with_name("INPUT")
+             .help("Input file (e.g. Map.c) or scenario (.ocs)")
+             .required(true)
+             .index(1))
+        .arg(Arg::with_name("OUTPUT")
+             .help("Output image (e.g. Matrix.png). An index of the maps is written next to it as .json")
+             .required(true)
+             .index(2))
+}
+
+/// A scenario parameter with the values to render.
+struct Axis {
+    id: String,
+    name: String,
+    values: Vec<(i32, String)>,
+}
+
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    let mut setup = Setup::new(matches)?;
+    let output_file = matches.value_of("OUTPUT").unwrap();
+    let scale = value_t!(matches.value_of("scale"), u32)
+                .chain_err(|| "invalid --scale option")?
+                .max(1);
+    let max_maps = value_t!(matches.value_of("max-maps"), usize)
+                   .chain_err(|| "invalid --max-maps option")?;
+    let vary = matches.values_of("vary").into_iter().flatten()
+        .map(parse_vary_arg)
+        .collect::<Result<Vec<_>>>()?;
+    let axes = match setup.scenpar() {
+        Some(scenpar) => axes(scenpar, &vary)?,
+        None => bail!("there is no ParameterDefs.txt with parameters to vary"),
+    };
+    let count = axes.iter().try_fold(1usize, |count, axis| count.checked_mul(axis.values.len()));
+    match count {
+        Some(count) if count <= max_maps => {}
+        _ => bail!("the parameters have {} combinations, more than the limit of {} maps; select fewer with --vary or raise --max-maps",
+                   count.map_or_else(|| "too many".to_string(), |c| c.to_string()), max_maps),
+    }
+
+    let mut sheet = ContactSheet::new();
+    sheet.scale(scale);
+    if let Some(columns) = matches.value_of("columns") {
+        sheet.columns(columns.parse().chain_err(|| "invalid --columns option")?);
+    }
+
+    // All maps use the same seed so that only the parameters make a difference.
+    let seed = setup.seed.unwrap_or_else(random_seed);
+    let combinations = combinations(&axes);
+    println!("Rendering {} maps…", combinations.len());
+    let mut errors = Vec::new();
+    for combination in &combinations {
+        let scenpar = setup.scenpar_mut().unwrap();
+        for &(axis, value) in combination {
+            scenpar.select(&axes[axis].id, value).chain_err(|| "invalid --vary")?;
+        }
+        let label = label(&axes, combination);
+        let map_handle = setup.build(matches)
+            .and_then(|mut cfg| cfg.seed(seed).render().chain_err(|| "map rendering failed"));
+        match map_handle {
+            Ok(map_handle) => {
+                sheet.add(map_handle.map_as_image(), &label);
+                errors.push(None);
+            },
+            Err(e) => {
+                let error = e.iter().map(|e| e.to_string()).collect::<Vec<_>>().join(": ");
+                eprintln!("{}: {}", label.replace('\n', ", "), error);
+                sheet.add_placeholder(&label);
+                errors.push(Some(error));
+            },
+        }
+    }
+
+    sheet.render()
+        .save(output_file)
+        .chain_err(|| "writing output image failed")?;
+
+    let index_file = Path::new(output_file).with_extension("json");
+    write_json(&index_file, &index(&setup, seed, &axes, &combinations, &errors, &sheet, output_file))
+}
+
+/// Parses an ID[=V1,V2,...] --vary argument.
+fn parse_vary_arg(arg: &str) -> Result<(String, Option<Vec<i32>>)> {
+    let mut parts = arg.splitn(2, '=');
+    let id = parts.next().unwrap();
+    if id.is_empty() {
+        bail!("invalid --vary '{}', expected ID or ID=V1,V2,...", arg);
+    }
+    let values = match parts.next() {
+        Some(values) => Some(values.split(',')
+            .map(|v| v.trim().parse().chain_err(|| format!("invalid --vary value '{}'", v)))
+            .collect::<Result<Vec<_>>>()?),
+        None => None,
+    };
+    Ok((id.to_owned(), values))
+}
+
+fn axes(scenpar: &Scenpar, vary: &[(String, Option<Vec<i32>>)]) -> Result<Vec<Axis>> {
+    let to_axis = |def: &ocmapgen::ScenparDef, values: &[i32]| Axis {
+        id: def.id.clone(),
+        name: def.name.clone(),
+        values: values.iter()
+            .map(|&v| (v, def.option(v).map(|o| o.name.clone()).unwrap_or_default()))
+            .collect(),
+    };
+    if vary.is_empty() {
+        return Ok(scenpar.defs().iter()
+            .filter(|def| !def.achievement)
+            .map(|def| to_axis(def, &def.options.iter().map(|o| o.value).collect::<Vec<_>>()))
+            .collect());
+    }
+    let mut axes = Vec::new();
+    for (id, values) in vary {
+        let def = match scenpar.def_by_id(id) {
+            Some(def) => def,
+            None => bail!("unknown scenario parameter {}", id),
+        };
+        let values = match values {
+            Some(values) => {
+                if let Some(v) = values.iter().find(|&&v| def.option(v).is_none()) {
+                    bail!("{} is not a value of {}", v, id);
+                }
+                values.clone()
+            },
+            None => def.options.iter().map(|o| o.value).collect(),
+        };
+        axes.push(to_axis(&def, &values));
+    }
+    Ok(axes)
+}
+
+/// Returns all combinations of values as (axis index, value) pairs, varying the last axis fastest.
+fn combinations(axes: &[Axis]) -> Vec<Vec<(usize, i32)>> {
+    let mut result = vec![Vec::new()];
+    for (i, axis) in axes.iter().enumerate() {
+        result = result.into_iter()
+            .flat_map(|combination| axis.values.iter().map(move |&(value, _)| {
+                let mut combination = combination.clone();
+                combination.push((i, value));
+                combination
+            }))
+            .collect();
+    }
+    result
+}
+
+fn label(axes: &[Axis], combination: &[(usize, i32)]) -> String {
+    combination.iter()
+        .map(|&(axis, value)| {
+            let name = &axes[axis].values.iter().find(|(v, _)| *v == value).unwrap().1;
+            if name.is_empty() {
+                format!("{}={}", axes[axis].id, value)
+            } else {
+                format!("{}={} ({})", axes[axis].id, value, name)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The .json index written next to the contact sheet.
+#[derive(Serialize)]
+struct Index<'a> {
+    input: &'a str,
+    image: &'a str,
+    seed: u32,
+    parameters: Vec<IndexParameter<'a>>,
+    maps: Vec<IndexMap<'a>>,
+}
+
+#[derive(Serialize)]
+struct IndexParameter<'a> {
+    id: &'a str,
+    name: &'a str,
+    values: Vec<i32>,
+}
+
+#[derive(Serialize)]
+struct IndexMap<'a> {
+    scenpar: OrderedMap<&'a str, i32>,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    error: Option<&'a str>,
+}
+
+fn index<'a>(setup: &'a Setup, seed: u32, axes: &'a [Axis], combinations: &[Vec<(usize, i32)>], errors: &'a [Option<String>], sheet: &ContactSheet, output_file: &'a str) -> Index<'a> {
+    let parameters = axes.iter()
+        .map(|axis| IndexParameter {
+            id: &axis.id,
+            name: &axis.name,
+            values: axis.values.iter().map(|&(v, _)| v).collect(),
+        })
+        .collect();
+    let maps = combinations.iter().zip(errors).enumerate()
+        .map(|(i, (combination, error))| {
+            let rect = sheet.tile_rect(i);
+            IndexMap {
+                scenpar: OrderedMap(combination.iter().map(|&(axis, value)| (axes[axis].id.as_str(), value)).collect()),
+                x: rect.x,
+                y: rect.y,
+                width: rect.width,
+                height: rect.height,
+                error: error.as_deref(),
+            }
+        })
+        .collect();
+    Index {
+        input: setup.input_file.to_str().unwrap(),
+        image: output_file,
+        seed,
+        parameters,
+        maps,
+    }
+}
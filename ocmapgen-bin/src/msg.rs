@@ -17,6 +17,8 @@ pub enum Response {
         bg: Option<ByteBuf>,
         warnings: Option<String>,
         script_output: Option<String>,
+        /// Seed to render the same map again.
+        seed: u32,
     },
     Error(String),
 }
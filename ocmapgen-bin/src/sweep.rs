@@ -0,0 +1,146 @@
+//! Renders the same map for many seeds.
+
+use crate::{Result, ResultExt, Setup, OrderedMap, render_args, write_json};
+
+use clap::{Arg, ArgMatches, App, SubCommand, value_t};
+use error_chain::bail;
+use ocmapgen::easy::Sweep;
+use serde_derive::Serialize;
+
+use std::ops::RangeInclusive;
+use std::path::Path;
+
+pub fn subcommand<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("sweep")
+        .about("Renders a map for many seeds into a contact sheet")
+        .args(&render_args())
+        .arg(Arg::with_name("seeds")
+             .long("seeds")
+             .help("Seeds to render, e.g. 1..64 (both inclusive)")
+             .takes_value(true)
+             .required(true)
+             .value_name("FIRST..LAST"))
+        .arg(Arg::with_name("columns")
+             .long("columns")
+             .help("Number of maps per row (defaults to about square)")
+             .takes_value(true))
+        .arg(Arg::with_name("scale")
+             .long("scale")
+             .help("Scale up each map by this factor")
+             .takes_value(true)
+             .default_value("1"))
+        .arg(Arg::with_name("stats")
+             .long("stats")
+             .help("Write map size and material usage per seed next to the output image as .json")
+             .takes_value(false))
+        .arg(Arg::with_name("INPUT")
+             .help("Input file (e.g. Map.c) or scenario (.ocs)")
+             .required(true)
+             .index(1))
+        .arg(Arg::with_name("OUTPUT")
+             .help("Output image (e.g. Sweep.png)")
+             .required(true)
+             .index(2))
+}
+
+pub fn run(matches: &ArgMatches) -> Result<()> {
+    let setup = Setup::new(matches)?;
+    let output_file = matches.value_of("OUTPUT").unwrap();
+    let seeds = parse_seeds(matches.value_of("seeds").unwrap())?;
+    let scale = value_t!(matches.value_of("scale"), u32)
+                .chain_err(|| "invalid --scale option")?;
+
+    // In u64, as 0..4294967295 has one seed more than fits into u32.
+    println!("Rendering {} maps…", u64::from(*seeds.end()) - u64::from(*seeds.start()) + 1);
+    let mut sweep = setup.build(matches)?.sweep(seeds);
+    for (seed, stats) in &sweep.maps {
+        if let Err(ref e) = stats {
+            eprintln!("seed {}: {}", seed, e);
+        }
+    }
+
+    sweep.sheet.scale(scale);
+    if let Some(columns) = matches.value_of("columns") {
+        sweep.sheet.columns(columns.parse().chain_err(|| "invalid --columns option")?);
+    }
+    sweep.sheet.render()
+        .save(output_file)
+        .chain_err(|| "writing output image failed")?;
+
+    if matches.is_present("stats") {
+        let stats_file = Path::new(output_file).with_extension("json");
+        write_json(&stats_file, &stats(&setup, &sweep, output_file))?;
+    }
+    Ok(())
+}
+
+/// Parses a FIRST..LAST seed range. A single seed is accepted as well.
+fn parse_seeds(arg: &str) -> Result<RangeInclusive<u32>> {
+    let parse = |s: &str| s.trim().parse::<u32>().chain_err(|| format!("invalid --seeds '{}'", arg));
+    let range = match arg.find("..") {
+        Some(pos) => parse(&arg[..pos])?..=parse(&arg[pos + 2..])?,
+        None => parse(arg)?..=parse(arg)?,
+    };
+    if range.is_empty() {
+        bail!("invalid --seeds '{}', the first seed is larger than the last", arg);
+    }
+    Ok(range)
+}
+
+/// The .json statistics written next to the contact sheet with --stats.
+#[derive(Serialize)]
+struct Stats<'a> {
+    input: &'a str,
+    image: &'a str,
+    maps: Vec<SeedStats<'a>>,
+}
+
+#[derive(Serialize)]
+struct SeedStats<'a> {
+    seed: u32,
+    x: u32,
+    y: u32,
+    width: u32,
+    height: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    map_width: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    map_height: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    materials: Option<OrderedMap<&'a str, u32>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+fn stats<'a>(setup: &'a Setup, sweep: &'a Sweep, output_file: &'a str) -> Stats<'a> {
+    let maps = sweep.maps.iter().enumerate()
+        .map(|(i, (seed, stats))| {
+            let rect = sweep.sheet.tile_rect(i);
+            let mut seed_stats = SeedStats {
+                seed: *seed,
+                x: rect.x,
+                y: rect.y,
+                width: rect.width,
+                height: rect.height,
+                map_width: None,
+                map_height: None,
+                materials: None,
+                error: None,
+            };
+            match stats {
+                Ok(stats) => {
+                    seed_stats.map_width = Some(stats.width);
+                    seed_stats.map_height = Some(stats.height);
+                    seed_stats.materials = Some(OrderedMap(stats.materials.iter().map(|(name, count)| (name.as_str(), *count)).collect()));
+                },
+                Err(e) => seed_stats.error = Some(e.to_string()),
+            }
+            seed_stats
+        })
+        .collect();
+    Stats {
+        input: setup.input_file.to_str().unwrap(),
+        image: output_file,
+        maps,
+    }
+}
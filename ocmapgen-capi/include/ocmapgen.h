@@ -0,0 +1,210 @@
+#ifndef INC_OCMAPGEN_H
+#define INC_OCMAPGEN_H
+
+/* Automatically generated by cbindgen from ocmapgen-capi/src. Do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+typedef enum OcmapgenMapType {
+  /**
+   * Infer the map type from the file name.
+   */
+  OCMAPGEN_MAP_TYPE_AUTO = 0,
+  OCMAPGEN_MAP_TYPE_LANDSCAPE_TXT = 1,
+  OCMAPGEN_MAP_TYPE_MAP_C = 2,
+} OcmapgenMapType;
+
+/**
+ * Map generator instance.
+ *
+ * Due to global state in the generator, there can only be one instance at any time.
+ */
+typedef struct OcmapgenContext OcmapgenContext;
+
+/**
+ * A rendered map. Holds copies of all data, so it is independent of the render configuration.
+ */
+typedef struct OcmapgenMap OcmapgenMap;
+
+/**
+ * Parameters for rendering a single map.
+ */
+typedef struct OcmapgenRenderConfig OcmapgenRenderConfig;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Returns the last error message of the current thread, or NULL if there was no error.
+ */
+const char *ocmapgen_last_error(void);
+
+/**
+ * Returns the version of the linked OpenClonk library.
+ *
+ * The returned string is valid for the lifetime of the calling thread.
+ */
+const char *ocmapgen_openclonk_version(void);
+
+/**
+ * Seeds the RNG used for map generation. Renders without a fixed seed draw their seed from it.
+ */
+void ocmapgen_seed_rng(uint32_t seed);
+
+/**
+ * Initializes the map generator.
+ *
+ * `base_path` has to be the OpenClonk base directory (containing Material.ocg, System.ocg,
+ * Objects.ocd) or a subdirectory.
+ */
+struct OcmapgenContext *ocmapgen_new(const char *base_path);
+
+/**
+ * Frees the map generator. All maps rendered with it have to be freed before.
+ */
+void ocmapgen_free(struct OcmapgenContext *ctx);
+
+/**
+ * Sets the result of GetStartupPlayerCount() in `Map.c`.
+ */
+void ocmapgen_set_startup_player_count(struct OcmapgenContext *ctx, int32_t count);
+
+/**
+ * Sets the result of GetStartupTeamCount() in `Map.c`.
+ */
+void ocmapgen_set_startup_team_count(struct OcmapgenContext *ctx, int32_t count);
+
+/**
+ * Loads ParameterDefs.txt from the given directory for use by all following `Map.c` renders.
+ */
+bool ocmapgen_load_scenpar(struct OcmapgenContext *ctx, const char *path);
+
+/**
+ * Creates a render configuration with default values (200x200 map, auto-detected map type).
+ */
+struct OcmapgenRenderConfig *ocmapgen_render_config_new(void);
+
+void ocmapgen_render_config_free(struct OcmapgenRenderConfig *cfg);
+
+void ocmapgen_render_config_set_map_type(struct OcmapgenRenderConfig *cfg,
+                                         enum OcmapgenMapType map_type);
+
+/**
+ * Sets the file name. The file name is used in error messages and to read the source if no
+ * source is set.
+ */
+void ocmapgen_render_config_set_filename(struct OcmapgenRenderConfig *cfg, const char *filename);
+
+/**
+ * Sets the map source code.
+ */
+void ocmapgen_render_config_set_source(struct OcmapgenRenderConfig *cfg, const char *source);
+
+/**
+ * Sets the output map size. Note that script Map.c can override the map size.
+ */
+void ocmapgen_render_config_set_size(struct OcmapgenRenderConfig *cfg,
+                                     uint32_t width,
+                                     uint32_t height);
+
+/**
+ * Sets the map width with random deviation like MapWidth in Scenario.txt: the width is picked
+ * from `standard ± random` using the seeded RNG and clamped to `[min, max]`. Overrides the width
+ * set with `ocmapgen_render_config_set_size`.
+ */
+void ocmapgen_render_config_set_map_width(struct OcmapgenRenderConfig *cfg,
+                                          int32_t standard,
+                                          int32_t random,
+                                          int32_t min,
+                                          int32_t max);
+
+/**
+ * Sets the map height with random deviation, see `ocmapgen_render_config_set_map_width`.
+ */
+void ocmapgen_render_config_set_map_height(struct OcmapgenRenderConfig *cfg,
+                                           int32_t standard,
+                                           int32_t random,
+                                           int32_t min,
+                                           int32_t max);
+
+/**
+ * Sets the script path for Algo=Script Landscape.txt maps.
+ */
+void ocmapgen_render_config_set_algo_script_path(struct OcmapgenRenderConfig *cfg,
+                                                 const char *path);
+
+/**
+ * Sets the number of players for this render. Defaults to the startup player count.
+ */
+void ocmapgen_render_config_set_players(struct OcmapgenRenderConfig *cfg, int32_t players);
+
+/**
+ * Makes the map grow in width with the number of players (up to four), like MapPlayerExtend in
+ * Scenario.txt.
+ */
+void ocmapgen_render_config_set_map_player_extend(struct OcmapgenRenderConfig *cfg,
+                                                  bool map_player_extend);
+
+/**
+ * Sets the RNG seed for this render, so that rendering again gives the same map. Without a seed,
+ * each render draws a new one, see `ocmapgen_map_seed`.
+ */
+void ocmapgen_render_config_set_seed(struct OcmapgenRenderConfig *cfg, uint32_t seed);
+
+/**
+ * Renders a map. Returns NULL on errors.
+ */
+struct OcmapgenMap *ocmapgen_render(struct OcmapgenContext *ctx,
+                                    const struct OcmapgenRenderConfig *cfg);
+
+void ocmapgen_map_free(struct OcmapgenMap *map);
+
+uint32_t ocmapgen_map_width(const struct OcmapgenMap *map);
+
+uint32_t ocmapgen_map_height(const struct OcmapgenMap *map);
+
+/**
+ * Returns the seed the map was rendered with.
+ */
+uint32_t ocmapgen_map_seed(const struct OcmapgenMap *map);
+
+/**
+ * Returns the foreground map as texture map indices (width * height bytes, row-major).
+ */
+const uint8_t *ocmapgen_map_fg_indices(const struct OcmapgenMap *map, size_t *len);
+
+/**
+ * Returns the background map as texture map indices (width * height bytes, row-major).
+ */
+const uint8_t *ocmapgen_map_bg_indices(const struct OcmapgenMap *map, size_t *len);
+
+/**
+ * Returns the foreground map as RGB image (width * height * 3 bytes, row-major).
+ */
+const uint8_t *ocmapgen_map_fg_image(const struct OcmapgenMap *map, size_t *len);
+
+/**
+ * Returns the background map as RGB image (width * height * 3 bytes, row-major).
+ */
+const uint8_t *ocmapgen_map_bg_image(const struct OcmapgenMap *map, size_t *len);
+
+/**
+ * Returns script warnings from parsing/linking/execution, or NULL if there were none.
+ */
+const char *ocmapgen_map_warnings(const struct OcmapgenMap *map);
+
+/**
+ * Returns script output (`Log()` function and friends), or NULL if there was none.
+ */
+const char *ocmapgen_map_script_output(const struct OcmapgenMap *map);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* INC_OCMAPGEN_H */
@@ -0,0 +1,401 @@
+//! C API for embedding the map generator into other applications.
+//!
+//! Functions returning pointers return NULL on failure, functions returning `bool` return false.
+//! The error message can then be retrieved with `ocmapgen_last_error()`.
+//!
+//! Strings and buffers returned by the library are owned by the object they were retrieved from
+//! and stay valid until that object is freed. All pointer arguments have to be valid pointers
+//! obtained from this library (or NUL-terminated strings) unless documented otherwise.
+
+#![allow(clippy::missing_safety_doc)]
+
+use ocmapgen::easy::{Easy, MapType, load_scenpar};
+use ocmapgen::{openclonk_version, seed_rng, Scenpar, ScenarioValue, Error};
+
+use std::cell::RefCell;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::ptr;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+fn set_error(err: &Error) {
+    let msg = err.iter()
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>()
+        .join(": ");
+    LAST_ERROR.with(|e| *e.borrow_mut() = Some(to_c_string(msg)));
+}
+
+fn to_c_string(s: String) -> CString {
+    // Interior NUL bytes would make the conversion fail, so cut the string there.
+    CString::new(s).unwrap_or_else(|e| {
+        let pos = e.nul_position();
+        let mut bytes = e.into_vec();
+        bytes.truncate(pos);
+        CString::new(bytes).unwrap()
+    })
+}
+
+unsafe fn from_c_str(s: *const c_char) -> Option<String> {
+    if s.is_null() {
+        None
+    } else {
+        Some(CStr::from_ptr(s).to_string_lossy().into_owned())
+    }
+}
+
+fn maybe_c_str(s: &Option<CString>) -> *const c_char {
+    s.as_ref().map(|s| s.as_ptr()).unwrap_or(ptr::null())
+}
+
+/// Returns the last error message of the current thread, or NULL if there was no error.
+#[no_mangle]
+pub extern "C" fn ocmapgen_last_error() -> *const c_char {
+    LAST_ERROR.with(|e| maybe_c_str(&e.borrow()))
+}
+
+/// Returns the version of the linked OpenClonk library.
+///
+/// The returned string is valid for the lifetime of the calling thread.
+#[no_mangle]
+pub extern "C" fn ocmapgen_openclonk_version() -> *const c_char {
+    thread_local! {
+        static VERSION: CString = to_c_string(openclonk_version());
+    }
+    VERSION.with(|v| v.as_ptr())
+}
+
+/// Seeds the RNG used for map generation. Renders without a fixed seed draw their seed from it.
+#[no_mangle]
+pub extern "C" fn ocmapgen_seed_rng(seed: u32) {
+    seed_rng(seed);
+}
+
+/// Map generator instance.
+///
+/// Due to global state in the generator, there can only be one instance at any time.
+pub struct OcmapgenContext {
+    easy: Easy,
+    scenpar: Option<Scenpar>,
+}
+
+/// Initializes the map generator.
+///
+/// `base_path` has to be the OpenClonk base directory (containing Material.ocg, System.ocg,
+/// Objects.ocd) or a subdirectory.
+#[no_mangle]
+pub unsafe extern "C" fn ocmapgen_new(base_path: *const c_char) -> *mut OcmapgenContext {
+    let base_path = match from_c_str(base_path) {
+        Some(p) => p,
+        None => {
+            set_error(&"base path is NULL".into());
+            return ptr::null_mut();
+        }
+    };
+    let result = Easy::new().and_then(|mut easy| {
+        easy.set_base_path(&base_path)?;
+        Ok(easy)
+    });
+    match result {
+        Ok(easy) => Box::into_raw(Box::new(OcmapgenContext { easy, scenpar: None })),
+        Err(e) => {
+            set_error(&e);
+            ptr::null_mut()
+        }
+    }
+}
+
+/// Frees the map generator. All maps rendered with it have to be freed before.
+#[no_mangle]
+pub unsafe extern "C" fn ocmapgen_free(ctx: *mut OcmapgenContext) {
+    if !ctx.is_null() {
+        drop(Box::from_raw(ctx));
+    }
+}
+
+/// Sets the result of GetStartupPlayerCount() in `Map.c`.
+#[no_mangle]
+pub unsafe extern "C" fn ocmapgen_set_startup_player_count(ctx: *mut OcmapgenContext, count: i32) {
+    (*ctx).easy.mapgen.set_startup_player_count(count);
+}
+
+/// Sets the result of GetStartupTeamCount() in `Map.c`.
+#[no_mangle]
+pub unsafe extern "C" fn ocmapgen_set_startup_team_count(ctx: *mut OcmapgenContext, count: i32) {
+    (*ctx).easy.mapgen.set_startup_team_count(count);
+}
+
+/// Loads ParameterDefs.txt from the given directory for use by all following `Map.c` renders.
+#[no_mangle]
+pub unsafe extern "C" fn ocmapgen_load_scenpar(ctx: *mut OcmapgenContext, path: *const c_char) -> bool {
+    let path = match from_c_str(path) {
+        Some(p) => p,
+        None => {
+            set_error(&"path is NULL".into());
+            return false;
+        }
+    };
+    match load_scenpar(&path) {
+        Ok(scenpar) => {
+            (*ctx).scenpar = Some(scenpar);
+            true
+        }
+        Err(e) => {
+            set_error(&e);
+            false
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq)]
+pub enum OcmapgenMapType {
+    /// Infer the map type from the file name.
+    Auto = 0,
+    LandscapeTxt = 1,
+    MapC = 2,
+}
+
+/// Parameters for rendering a single map.
+pub struct OcmapgenRenderConfig {
+    map_type: OcmapgenMapType,
+    filename: Option<String>,
+    source: Option<String>,
+    width: u32,
+    height: u32,
+    algo_script_path: Option<String>,
+    map_width: Option<ScenarioValue>,
+    map_height: Option<ScenarioValue>,
+    players: Option<i32>,
+    map_player_extend: bool,
+    seed: Option<u32>,
+}
+
+/// Creates a render configuration with default values (200x200 map, auto-detected map type).
+#[no_mangle]
+pub extern "C" fn ocmapgen_render_config_new() -> *mut OcmapgenRenderConfig {
+    Box::into_raw(Box::new(OcmapgenRenderConfig {
+        map_type: OcmapgenMapType::Auto,
+        filename: None,
+        source: None,
+        width: 200,
+        height: 200,
+        algo_script_path: None,
+        map_width: None,
+        map_height: None,
+        players: None,
+        map_player_extend: false,
+        seed: None,
+    }))
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ocmapgen_render_config_free(cfg: *mut OcmapgenRenderConfig) {
+    if !cfg.is_null() {
+        drop(Box::from_raw(cfg));
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ocmapgen_render_config_set_map_type(cfg: *mut OcmapgenRenderConfig, map_type: OcmapgenMapType) {
+    (*cfg).map_type = map_type;
+}
+
+/// Sets the file name. The file name is used in error messages and to read the source if no
+/// source is set.
+#[no_mangle]
+pub unsafe extern "C" fn ocmapgen_render_config_set_filename(cfg: *mut OcmapgenRenderConfig, filename: *const c_char) {
+    (*cfg).filename = from_c_str(filename);
+}
+
+/// Sets the map source code.
+#[no_mangle]
+pub unsafe extern "C" fn ocmapgen_render_config_set_source(cfg: *mut OcmapgenRenderConfig, source: *const c_char) {
+    (*cfg).source = from_c_str(source);
+}
+
+/// Sets the output map size. Note that script Map.c can override the map size.
+#[no_mangle]
+pub unsafe extern "C" fn ocmapgen_render_config_set_size(cfg: *mut OcmapgenRenderConfig, width: u32, height: u32) {
+    (*cfg).width = width;
+    (*cfg).height = height;
+}
+
+/// Sets the map width with random deviation like MapWidth in Scenario.txt: the width is picked
+/// from `standard ± random` using the seeded RNG and clamped to `[min, max]`. Overrides the width
+/// set with `ocmapgen_render_config_set_size`.
+#[no_mangle]
+pub unsafe extern "C" fn ocmapgen_render_config_set_map_width(cfg: *mut OcmapgenRenderConfig, standard: i32, random: i32, min: i32, max: i32) {
+    (*cfg).map_width = Some(ScenarioValue::new(standard, random, min, max));
+}
+
+/// Sets the map height with random deviation, see `ocmapgen_render_config_set_map_width`.
+#[no_mangle]
+pub unsafe extern "C" fn ocmapgen_render_config_set_map_height(cfg: *mut OcmapgenRenderConfig, standard: i32, random: i32, min: i32, max: i32) {
+    (*cfg).map_height = Some(ScenarioValue::new(standard, random, min, max));
+}
+
+/// Sets the script path for Algo=Script Landscape.txt maps.
+#[no_mangle]
+pub unsafe extern "C" fn ocmapgen_render_config_set_algo_script_path(cfg: *mut OcmapgenRenderConfig, path: *const c_char) {
+    (*cfg).algo_script_path = from_c_str(path);
+}
+
+/// Sets the number of players for this render. Defaults to the startup player count.
+#[no_mangle]
+pub unsafe extern "C" fn ocmapgen_render_config_set_players(cfg: *mut OcmapgenRenderConfig, players: i32) {
+    (*cfg).players = Some(players);
+}
+
+/// Makes the map grow in width with the number of players (up to four), like MapPlayerExtend in
+/// Scenario.txt.
+#[no_mangle]
+pub unsafe extern "C" fn ocmapgen_render_config_set_map_player_extend(cfg: *mut OcmapgenRenderConfig, map_player_extend: bool) {
+    (*cfg).map_player_extend = map_player_extend;
+}
+
+/// Sets the RNG seed for this render, so that rendering again gives the same map. Without a seed,
+/// each render draws a new one, see `ocmapgen_map_seed`.
+#[no_mangle]
+pub unsafe extern "C" fn ocmapgen_render_config_set_seed(cfg: *mut OcmapgenRenderConfig, seed: u32) {
+    (*cfg).seed = Some(seed);
+}
+
+/// A rendered map. Holds copies of all data, so it is independent of the render configuration.
+pub struct OcmapgenMap {
+    width: u32,
+    height: u32,
+    seed: u32,
+    fg: Vec<u8>,
+    bg: Vec<u8>,
+    fg_image: Vec<u8>,
+    bg_image: Vec<u8>,
+    warnings: Option<CString>,
+    script_output: Option<CString>,
+}
+
+/// Renders a map. Returns NULL on errors.
+#[no_mangle]
+pub unsafe extern "C" fn ocmapgen_render(ctx: *mut OcmapgenContext, cfg: *const OcmapgenRenderConfig) -> *mut OcmapgenMap {
+    let ctx = &*ctx;
+    let cfg = &*cfg;
+    let mut rc = ctx.easy.build();
+    rc.width(cfg.width)
+      .height(cfg.height)
+      .map_player_extend(cfg.map_player_extend);
+    if let Some(players) = cfg.players {
+        rc.players(players);
+    }
+    if let Some(seed) = cfg.seed {
+        rc.seed(seed);
+    }
+    if let Some(map_width) = cfg.map_width {
+        rc.map_width(map_width);
+    }
+    if let Some(map_height) = cfg.map_height {
+        rc.map_height(map_height);
+    }
+    match cfg.map_type {
+        OcmapgenMapType::Auto => (),
+        OcmapgenMapType::LandscapeTxt => { rc.map_type(MapType::LandscapeTxt); },
+        OcmapgenMapType::MapC => { rc.map_type(MapType::MapC); },
+    }
+    if let Some(ref filename) = cfg.filename {
+        rc.filename(filename);
+    }
+    if let Some(ref source) = cfg.source {
+        rc.source(source);
+    }
+    if let Some(ref path) = cfg.algo_script_path {
+        rc.algo_script_path(path);
+    }
+    if let Some(ref scenpar) = ctx.scenpar {
+        rc.scenpar(scenpar);
+    }
+    match rc.render() {
+        Ok(handle) => Box::into_raw(Box::new(OcmapgenMap {
+            width: handle.width(),
+            height: handle.height(),
+            seed: handle.seed(),
+            fg: handle.map_indices(),
+            bg: handle.map_bg_indices(),
+            fg_image: handle.map_as_image().into_raw(),
+            bg_image: handle.map_bg_as_image().into_raw(),
+            warnings: handle.warnings().map(to_c_string),
+            script_output: handle.script_output().map(to_c_string),
+        })),
+        Err(e) => {
+            set_error(&e);
+            ptr::null_mut()
+        }
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ocmapgen_map_free(map: *mut OcmapgenMap) {
+    if !map.is_null() {
+        drop(Box::from_raw(map));
+    }
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ocmapgen_map_width(map: *const OcmapgenMap) -> u32 {
+    (*map).width
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn ocmapgen_map_height(map: *const OcmapgenMap) -> u32 {
+    (*map).height
+}
+
+/// Returns the seed the map was rendered with.
+#[no_mangle]
+pub unsafe extern "C" fn ocmapgen_map_seed(map: *const OcmapgenMap) -> u32 {
+    (*map).seed
+}
+
+/// Returns the foreground map as texture map indices (width * height bytes, row-major).
+#[no_mangle]
+pub unsafe extern "C" fn ocmapgen_map_fg_indices(map: *const OcmapgenMap, len: *mut usize) -> *const u8 {
+    buffer(&(*map).fg, len)
+}
+
+/// Returns the background map as texture map indices (width * height bytes, row-major).
+#[no_mangle]
+pub unsafe extern "C" fn ocmapgen_map_bg_indices(map: *const OcmapgenMap, len: *mut usize) -> *const u8 {
+    buffer(&(*map).bg, len)
+}
+
+/// Returns the foreground map as RGB image (width * height * 3 bytes, row-major).
+#[no_mangle]
+pub unsafe extern "C" fn ocmapgen_map_fg_image(map: *const OcmapgenMap, len: *mut usize) -> *const u8 {
+    buffer(&(*map).fg_image, len)
+}
+
+/// Returns the background map as RGB image (width * height * 3 bytes, row-major).
+#[no_mangle]
+pub unsafe extern "C" fn ocmapgen_map_bg_image(map: *const OcmapgenMap, len: *mut usize) -> *const u8 {
+    buffer(&(*map).bg_image, len)
+}
+
+unsafe fn buffer(data: &[u8], len: *mut usize) -> *const u8 {
+    if !len.is_null() {
+        *len = data.len();
+    }
+    data.as_ptr()
+}
+
+/// Returns script warnings from parsing/linking/execution, or NULL if there were none.
+#[no_mangle]
+pub unsafe extern "C" fn ocmapgen_map_warnings(map: *const OcmapgenMap) -> *const c_char {
+    maybe_c_str(&(*map).warnings)
+}
+
+/// Returns script output (`Log()` function and friends), or NULL if there was none.
+#[no_mangle]
+pub unsafe extern "C" fn ocmapgen_map_script_output(map: *const OcmapgenMap) -> *const c_char {
+    maybe_c_str(&(*map).script_output)
+}
@@ -0,0 +1,43 @@
+use regex::Regex;
+use std::io;
+use std::io::prelude::*;
+use std::fs::File;
+use std::env;
+
+const CAPI_SOURCE: &str = "../ocmapgen-capi/src/lib.rs";
+
+fn main() {
+    if !env::var("TARGET").unwrap().contains("emscripten") {
+        println!("cargo:warning=ocmapgen-wasm should be built with --target wasm32-unknown-emscripten");
+        return;
+    }
+
+    // Export all functions of the C API.
+    let source = read_file(CAPI_SOURCE).unwrap();
+    let re = Regex::new(r#"(?m)^pub (?:unsafe )?extern "C" fn (\w+)"#).unwrap();
+    let mut exported: Vec<String> = re.captures_iter(&source)
+        .map(|c| format!("_{}", &c[1]))
+        .collect();
+    exported.extend(["_main", "_malloc", "_free"].iter().map(|s| s.to_string()));
+    println!("cargo:rerun-if-changed={}", CAPI_SOURCE);
+
+    let link_args = [
+        "-sMODULARIZE=1".to_string(),
+        "-sEXPORT_NAME=createOcmapgenModule".to_string(),
+        "-sALLOW_MEMORY_GROWTH=1".to_string(),
+        "-sUSE_ZLIB=1".to_string(),
+        "-fexceptions".to_string(),
+        "-sFORCE_FILESYSTEM=1".to_string(),
+        "-sEXPORTED_RUNTIME_METHODS=ccall,FS,UTF8ToString,stringToNewUTF8,HEAPU8".to_string(),
+        format!("-sEXPORTED_FUNCTIONS={}", exported.join(",")),
+    ];
+    for arg in link_args.iter() {
+        println!("cargo:rustc-link-arg-bins={}", arg);
+    }
+}
+
+fn read_file(path: &str) -> io::Result<String> {
+    let mut contents = String::new();
+    File::open(path)?.read_to_string(&mut contents)?;
+    Ok(contents)
+}
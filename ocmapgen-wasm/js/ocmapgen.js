@@ -0,0 +1,158 @@
+// JavaScript API for the WebAssembly build of ocmapgen.
+//
+// The map generator runs completely in the browser (or node). Material.ocg,
+// System.ocg and Objects.ocd are loaded from memory, so they can be fetched
+// as packed groups from anywhere.
+//
+// Example:
+//
+//   const createOcmapgenModule = require('./ocmapgen-module.js') // emscripten output
+//   const mapgen = await MapGen.create(createOcmapgenModule, {
+//   	assets: {
+//   		'Material.ocg': materialOcg, // Uint8Array with packed group
+//   		'System.ocg': systemOcg,
+//   		'Objects.ocd': objectsOcd,
+//   	},
+//   })
+//   const {width, height, fg} = mapgen.render({source, mapType: 'Map.c'})
+
+const MAP_TYPES = {
+	'auto': 0,
+	'Landscape.txt': 1,
+	'Map.c': 2,
+}
+
+const defaultOptions = {
+	basePath: '/planet',
+	assets: {},
+	moduleOptions: {},
+}
+
+class MapGen {
+	// Instantiates the emscripten module and initializes the map generator.
+	//
+	// Options:
+	//  - assets: Object mapping file names relative to basePath to their
+	//    contents (Uint8Array), e.g. packed Material.ocg, System.ocg and
+	//    Objects.ocd groups. Names may contain directories.
+	//  - basePath: Location of the assets in the virtual file system.
+	//  - moduleOptions: Passed to the emscripten module factory.
+	static async create(createModule, options) {
+		options = Object.assign({}, defaultOptions, options)
+		const module = await createModule(options.moduleOptions)
+		return new MapGen(module, options)
+	}
+
+	// Use MapGen.create() instead.
+	constructor(module, options) {
+		this.module = module
+		this.options = options
+		for (let [name, data] of Object.entries(options.assets))
+			this._writeFile(`${options.basePath}/${name}`, data)
+		this.ctx = this._withString(options.basePath, p => module._ocmapgen_new(p))
+		if (!this.ctx)
+			throw this._lastError()
+	}
+
+	// Renders a map.
+	//
+	// Options:
+	//  - source: Map.c or Landscape.txt source code.
+	//  - mapType: 'Map.c', 'Landscape.txt' or 'auto' (infer from filename).
+	//  - filename: Used in error messages.
+	//  - width, height: Map size. Note that Map.c can override the map size.
+	//  - seed, players, teams: correspond to ocmapgen options. Without a seed, a new one is
+	//    drawn for each render.
+	//  - playerExtend: Grow the map width with the number of players.
+	//
+	// Returns {width, height, seed, fg, bg, fgIndices, bgIndices, warnings, scriptOutput}
+	// with `fg` and `bg` as RGB data and the indices as texture map indices.
+	render(options) {
+		const m = this.module
+		const {source, filename, seed, players, teams} = options
+		const mapType = options.mapType || 'auto'
+		if (!(mapType in MAP_TYPES))
+			throw new Error(`invalid map type ${mapType}`)
+		if (teams !== undefined)
+			m._ocmapgen_set_startup_team_count(this.ctx, teams)
+
+		const cfg = m._ocmapgen_render_config_new()
+		try {
+			m._ocmapgen_render_config_set_map_type(cfg, MAP_TYPES[mapType])
+			m._ocmapgen_render_config_set_size(cfg, options.width || 200, options.height || 200)
+			m._ocmapgen_render_config_set_map_player_extend(cfg, !!options.playerExtend)
+			if (players !== undefined)
+				m._ocmapgen_render_config_set_players(cfg, players)
+			if (seed !== undefined)
+				m._ocmapgen_render_config_set_seed(cfg, seed >>> 0)
+			if (source !== undefined)
+				this._withString(source, p => m._ocmapgen_render_config_set_source(cfg, p))
+			if (filename !== undefined)
+				this._withString(filename, p => m._ocmapgen_render_config_set_filename(cfg, p))
+
+			const map = m._ocmapgen_render(this.ctx, cfg)
+			if (!map)
+				throw this._lastError()
+			try {
+				return this._readMap(map)
+			} finally {
+				m._ocmapgen_map_free(map)
+			}
+		} finally {
+			m._ocmapgen_render_config_free(cfg)
+		}
+	}
+
+	// Frees the map generator. The instance cannot be used afterwards.
+	end() {
+		if (this.ctx)
+			this.module._ocmapgen_free(this.ctx)
+		this.ctx = null
+	}
+
+	_readMap(map) {
+		const m = this.module
+		const width = m._ocmapgen_map_width(map)
+		const height = m._ocmapgen_map_height(map)
+		const size = width * height
+		// Copy everything as the map will be freed afterwards.
+		const copy = (ptr, len) => m.HEAPU8.slice(ptr, ptr + len)
+		return {
+			width,
+			height,
+			seed: m._ocmapgen_map_seed(map) >>> 0,
+			fg: copy(m._ocmapgen_map_fg_image(map, 0), 3 * size),
+			bg: copy(m._ocmapgen_map_bg_image(map, 0), 3 * size),
+			fgIndices: copy(m._ocmapgen_map_fg_indices(map, 0), size),
+			bgIndices: copy(m._ocmapgen_map_bg_indices(map, 0), size),
+			warnings: this._maybeString(m._ocmapgen_map_warnings(map)),
+			scriptOutput: this._maybeString(m._ocmapgen_map_script_output(map)),
+		}
+	}
+
+	_writeFile(path, data) {
+		const {FS} = this.module
+		FS.mkdirTree(path.substring(0, path.lastIndexOf('/')))
+		FS.writeFile(path, data)
+	}
+
+	// Calls fn with a pointer to a temporary C string.
+	_withString(str, fn) {
+		const ptr = this.module.stringToNewUTF8(str)
+		try {
+			return fn(ptr)
+		} finally {
+			this.module._free(ptr)
+		}
+	}
+
+	_maybeString(ptr) {
+		return ptr ? this.module.UTF8ToString(ptr) : null
+	}
+
+	_lastError() {
+		return new Error(this._maybeString(this.module._ocmapgen_last_error()) || 'unknown error')
+	}
+}
+
+module.exports = {MapGen}
@@ -0,0 +1,11 @@
+//! WebAssembly build of the map generator.
+//!
+//! This executable only exists to link the C API into an emscripten module. All functionality is
+//! exported from `ocmapgen-capi` and wrapped by `js/ocmapgen.js`.
+
+// Pull in the exported functions.
+pub use ocmapgen_capi::*;
+
+fn main() {
+    // Nothing to do, the module is driven from JavaScript.
+}
@@ -0,0 +1,303 @@
+use crate::errors::*;
+use crate::{Group, GroupEntry};
+
+use std::fmt;
+use std::fs::{self, File};
+use std::io::prelude::*;
+use std::path::{Component, Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use error_chain::bail;
+
+/// Game data (Material.ocg, System.ocg, Objects.ocd, ...) supplied from memory.
+///
+/// C4Group can only read from the file system, so the bundle is materialized in a temporary
+/// directory which is removed again when the bundle is dropped. Packed groups are stored as single
+/// files, so adding a packed `Material.ocg` needs only one call to `add_file`.
+pub struct AssetBundle {
+    path: PathBuf,
+}
+
+impl AssetBundle {
+    /// Creates an empty bundle.
+    pub fn new() -> Result<AssetBundle> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or(0);
+        let path = std::env::temp_dir().join(format!("ocmapgen-{}-{}-{}",
+                                                     process::id(),
+                                                     COUNTER.fetch_add(1, Ordering::SeqCst),
+                                                     nanos));
+        fs::create_dir(&path)
+            .chain_err(|| format!("couldn't create asset directory {}", path.display()))?;
+        Ok(AssetBundle { path })
+    }
+
+    /// Creates a bundle from (name, data) pairs. See `add_file`.
+    pub fn from_files<'a, I>(files: I) -> Result<AssetBundle>
+            where I: IntoIterator<Item = (&'a str, &'a [u8])> {
+        let mut bundle = AssetBundle::new()?;
+        for (name, data) in files {
+            bundle.add_file(name, data)?;
+        }
+        Ok(bundle)
+    }
+
+    /// Adds a file to the bundle.
+    ///
+    /// The name is relative to the bundle root and may contain directories separated by '/', e.g.
+    /// `System.ocg/Map.c` for an unpacked group or just `System.ocg` for a packed one.
+    pub fn add_file(&mut self, name: &str, data: &[u8]) -> Result<()> {
+        let relative = Path::new(name);
+        let valid = relative.components().all(|c| matches!(c, Component::Normal(_)));
+        if !valid || name.is_empty() {
+            bail!("invalid asset name '{}'", name);
+        }
+        let path = self.path.join(relative);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        File::create(&path)?.write_all(data)?;
+        Ok(())
+    }
+
+    /// Returns the directory the bundle is stored in.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for AssetBundle {
+    fn drop(&mut self) {
+        // Nothing sensible to do on errors here.
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Explicit configuration of the game data to load.
+///
+/// Use `Easy::load_assets` to load it. `AssetConfig::discover` finds the same files as
+/// `Easy::set_base_path`, so the result can be inspected or adjusted before loading.
+#[derive(Clone, Debug, Default)]
+pub struct AssetConfig {
+    materials: Vec<PathBuf>,
+    systems: Vec<PathBuf>,
+    objects: Option<PathBuf>,
+    map_library: Option<PathBuf>,
+    definitions: Vec<PathBuf>,
+}
+
+impl AssetConfig {
+    pub fn new() -> AssetConfig {
+        AssetConfig::default()
+    }
+
+    /// Searches the given directory and its parents for game data.
+    ///
+    /// This looks for the closest Material.ocg. If its TexMap.txt specifies OverloadMaterials or
+    /// OverloadTextures, Material.ocg groups further up are added as well. The search ends at the
+    /// base directory containing System.ocg and Objects.ocd.
+    ///
+    /// Scenarios (.ocs) and scenario folders (.ocf) on the way may contain their own System.ocg
+    /// and definitions. These are added after the ones from the base directory, from the outermost
+    /// folder to the scenario, which is the order the engine loads them in.
+    pub fn discover<P: AsRef<Path>>(path: P) -> Result<AssetConfig> {
+        let mut path = path.as_ref().canonicalize()?;
+        let mut config = AssetConfig::new();
+        let mut search_for_overload = false;
+        let mut local_systems = Vec::new();
+        let mut local_definitions = Vec::new();
+        loop {
+            let local = if is_scenario_or_folder(&path) { Some(local_assets(&path)?) } else { None };
+
+            if config.materials.is_empty() || search_for_overload {
+                // Scenarios may be packed, so their Material.ocg has to be looked up via C4Group.
+                let material_ocg = match local {
+                    Some(ref local) => local.material.clone(),
+                    None => Some(path.join("Material.ocg")).filter(|p| p.exists()),
+                };
+                if let Some(material_ocg) = material_ocg {
+                    let mut group = Group::open(material_ocg.to_str().unwrap(), false)?;
+                    search_for_overload = overloads(&mut group)?;
+                    // Groups further up are overloaded by the ones found before.
+                    config.materials.insert(0, material_ocg);
+                }
+            }
+
+            let system_ocg = path.join("System.ocg");
+            let objects_ocd = path.join("Objects.ocd");
+            if system_ocg.exists() && objects_ocd.exists() {
+                // Done, we found the root.
+                config.systems.push(system_ocg);
+                config.objects = Some(objects_ocd);
+                break;
+            }
+
+            if let Some(local) = local {
+                // Inserting at the front again, so the outermost folder comes first.
+                if let Some(system) = local.system {
+                    local_systems.insert(0, system);
+                }
+                for (i, def) in local.definitions.into_iter().enumerate() {
+                    local_definitions.insert(i, def);
+                }
+            }
+
+            if !path.pop() {
+                bail!("couldn't find base path");
+            }
+        }
+        if config.materials.is_empty() {
+            bail!("couldn't find Material.ocg");
+        }
+        config.systems.append(&mut local_systems);
+        config.definitions = local_definitions;
+        Ok(config)
+    }
+
+    /// Adds a Material.ocg group. Groups added later overload the ones added before.
+    pub fn material<P: AsRef<Path>>(&mut self, path: P) -> &mut AssetConfig {
+        self.materials.push(path.as_ref().to_owned());
+        self
+    }
+
+    /// Adds a System.ocg group. Scripts are loaded in the order the groups are added.
+    pub fn system<P: AsRef<Path>>(&mut self, path: P) -> &mut AssetConfig {
+        self.systems.push(path.as_ref().to_owned());
+        self
+    }
+
+    /// Sets the Objects.ocd group to look up Library_Map in.
+    pub fn objects<P: AsRef<Path>>(&mut self, path: P) -> &mut AssetConfig {
+        self.objects = Some(path.as_ref().to_owned());
+        self
+    }
+
+    /// Sets the location of Library_Map (usually `Objects.ocd/Libraries.ocd/Map.ocd`) directly.
+    /// Takes precedence over `objects`.
+    pub fn map_library<P: AsRef<Path>>(&mut self, path: P) -> &mut AssetConfig {
+        self.map_library = Some(path.as_ref().to_owned());
+        self
+    }
+
+    /// Adds a .ocd group. Its script and the ones of all nested definitions are loaded after
+    /// Library_Map, in the order the groups are added. Later definitions overload earlier ones
+    /// with the same ID.
+    pub fn definition<P: AsRef<Path>>(&mut self, path: P) -> &mut AssetConfig {
+        self.definitions.push(path.as_ref().to_owned());
+        self
+    }
+
+    pub fn material_paths(&self) -> &[PathBuf] {
+        &self.materials
+    }
+
+    pub fn system_paths(&self) -> &[PathBuf] {
+        &self.systems
+    }
+
+    pub fn objects_path(&self) -> Option<&PathBuf> {
+        self.objects.as_ref()
+    }
+
+    pub fn map_library_path(&self) -> Option<&PathBuf> {
+        self.map_library.as_ref()
+    }
+
+    pub fn definition_paths(&self) -> &[PathBuf] {
+        &self.definitions
+    }
+}
+
+/// Checks whether the TexMap.txt of a Material.ocg sets OverloadMaterials or OverloadTextures,
+/// the same way as `C4TextureMap::LoadMap`.
+fn overloads(material_ocg: &mut Group) -> Result<bool> {
+    if material_ocg.find("TexMap.txt")?.is_empty() {
+        return Ok(false);
+    }
+    let texmap = material_ocg.load_entry("TexMap.txt")?;
+    Ok(texmap.split(|&b| b == b'\n')
+        .any(|line| line.starts_with(b"OverloadMaterials") || line.starts_with(b"OverloadTextures")))
+}
+
+/// Checks whether the path points to a scenario or scenario folder, which may contain local
+/// System.ocg groups and definitions.
+fn is_scenario_or_folder(path: &Path) -> bool {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) => ext.eq_ignore_ascii_case("ocs") || ext.eq_ignore_ascii_case("ocf"),
+        None => false,
+    }
+}
+
+/// Material.ocg, System.ocg and definitions directly inside a scenario or folder.
+struct LocalAssets {
+    material: Option<PathBuf>,
+    system: Option<PathBuf>,
+    definitions: Vec<PathBuf>,
+}
+
+/// Finds the assets directly inside a scenario or folder, which may be packed.
+fn local_assets(path: &Path) -> Result<LocalAssets> {
+    let mut group = Group::open(path.to_str().unwrap(), false)
+        .chain_err(|| format!("couldn't open {}", path.display()))?;
+    let mut local = LocalAssets { material: None, system: None, definitions: Vec::new() };
+    for GroupEntry { name, raw_name, .. } in group.entries()? {
+        if name.as_bytes() != &raw_name[..] {
+            continue; // asset paths are passed on as str
+        }
+        let lower = name.to_lowercase();
+        if lower == "material.ocg" {
+            local.material = Some(path.join(name));
+        } else if lower == "system.ocg" {
+            local.system = Some(path.join(name));
+        } else if lower.ends_with(".ocd") {
+            local.definitions.push(path.join(name));
+        }
+    }
+    Ok(local)
+}
+
+/// Describes what `Easy::load_assets` loaded from where.
+#[derive(Clone, Debug, Default)]
+pub struct AssetReport {
+    /// Material.ocg groups in overload order with the number of materials loaded from each.
+    pub materials: Vec<(PathBuf, u32)>,
+    /// Material.ocg groups in overload order with the number of textures loaded from each.
+    pub textures: Vec<(PathBuf, usize)>,
+    /// Material.ocg group the texture map was loaded from with the number of entries.
+    pub texture_map: Option<(PathBuf, u32)>,
+    /// System.ocg groups in load order.
+    pub systems: Vec<PathBuf>,
+    /// Group Library_Map was loaded from.
+    pub map_library: Option<PathBuf>,
+    /// Additional definitions in load order with the group each was loaded from.
+    pub definitions: Vec<(PathBuf, String)>,
+}
+
+impl fmt::Display for AssetReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (path, num) in &self.materials {
+            writeln!(f, "materials: {} from {}", num, path.display())?;
+        }
+        for (path, num) in &self.textures {
+            writeln!(f, "textures: {} from {}", num, path.display())?;
+        }
+        if let Some((ref path, num)) = self.texture_map {
+            writeln!(f, "texture map: {} entries from {}", num, path.display())?;
+        }
+        for path in &self.systems {
+            writeln!(f, "system: {}", path.display())?;
+        }
+        match self.map_library {
+            Some(ref path) => writeln!(f, "map library: {}", path.display())?,
+            None => writeln!(f, "map library: none")?,
+        }
+        for (path, id) in &self.definitions {
+            writeln!(f, "definition: {} from {}", id, path.display())?;
+        }
+        Ok(())
+    }
+}
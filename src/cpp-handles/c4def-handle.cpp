@@ -19,10 +19,16 @@
 #include "object/C4DefList.h"
 #include "lib/StdMeshLoader.h"
 
+#include <vector>
+
 /* This is a simple implementation of C4DefList for what is required by
  * mape. We cannot link the full implementation since it would introduce
  * a dependency on C4Game, and therefore the rest of the engine. */
 
+/* Definitions replaced by an overload. Scripts may still reference them,
+ * so they are only deleted when the list is cleared. */
+static std::vector<C4Def*> OverloadedDefs;
+
 C4Def::C4Def(): Script(), C4PropListStatic(ScriptEngine.GetPropList(), nullptr, nullptr)
 {
         Script.SetDef(this);
@@ -57,6 +63,9 @@ void C4DefList::Clear()
 		FirstDef = FirstDef->Next;
 		delete out;
 	}
+	for(C4Def* def : OverloadedDefs)
+		delete def;
+	OverloadedDefs.clear();
 }
 
 C4Def* C4DefList::ID2Def(C4ID id)
@@ -95,7 +104,22 @@ int C4DefList::GetDefCount()
 
 bool C4DefList::Add(C4Def* def, bool fOverload)
 {
-	assert(ID2Def(def->id) == nullptr);
+	C4Def* old = ID2Def(def->id);
+	if(old && !fOverload) return false;
+
+	if(old)
+	{
+		for(C4Def** cdef = &FirstDef; *cdef != nullptr; cdef = &(*cdef)->Next)
+		{
+			if(*cdef == old)
+			{
+				*cdef = old->Next;
+				break;
+			}
+		}
+		old->Next = nullptr;
+		OverloadedDefs.push_back(old);
+	}
 
 	def->Next = FirstDef;
 	FirstDef = def;
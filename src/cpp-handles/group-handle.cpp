@@ -15,11 +15,40 @@
 
 #include "C4Include.h"
 #include "c4group/C4Group.h"
+#include "platform/StdFile.h"
 #include "group-handle.h"
 
+#include <cstdio>
+
 #define GROUP_TO_HANDLE(group) (reinterpret_cast<C4GroupHandle*>(group))
 #define HANDLE_TO_GROUP(handle) (reinterpret_cast<C4Group*>(handle))
 
+namespace {
+
+// C4Group only gives subclasses access to its entries, like C4GroupEx in the updater.
+class GroupEntryAccess: public C4Group
+{
+public:
+	static C4GroupEntry* GetEntry(C4Group* group, const char* name)
+	{
+		return (group->*(&GroupEntryAccess::GetEntry))(name);
+	}
+};
+
+// Packed groups are gzip files with a modified magic number.
+bool IsPackedGroupFile(const char* path)
+{
+	FILE* file = fopen(path, "rb");
+	if (!file) return false;
+	unsigned char magic[2] = { 0, 0 };
+	size_t read = fread(magic, 1, 2, file);
+	fclose(file);
+	return read == 2 && magic[0] == 0x1e && magic[1] == 0x8c;
+}
+
+}
+
+
 extern "C" {
 
 C4GroupHandle* c4_group_handle_new(void)
@@ -61,11 +90,26 @@ char* c4_group_handle_get_full_name(C4GroupHandle* handle)
   return res;
 }
 
+void c4_group_handle_free_string(char* string)
+{
+  free(string);
+}
+
 void c4_group_handle_reset_search(C4GroupHandle* handle)
 {
   HANDLE_TO_GROUP(handle)->ResetSearch();
 }
 
+char* c4_group_handle_find_next_entry_name(C4GroupHandle* handle, const char* wildcard, size_t* size)
+{
+  StdStrBuf filename;
+  if (!HANDLE_TO_GROUP(handle)->FindNextEntry(wildcard, &filename, size))
+    return nullptr;
+  char* res = static_cast<char*>(malloc(filename.getSize()*sizeof(char)));
+  memcpy(res, filename.getData(), filename.getSize());
+  return res;
+}
+
 bool c4_group_handle_find_next_entry(C4GroupHandle* handle, const char* wildcard, size_t* size, char* filename, bool start_at_filename)
 {
   return HANDLE_TO_GROUP(handle)->FindNextEntry(wildcard, filename, size, start_at_filename);
@@ -91,10 +135,73 @@ bool c4_group_handle_read(C4GroupHandle* handle, void* buffer, size_t size)
   return HANDLE_TO_GROUP(handle)->Read(buffer, size);
 }
 
+bool c4_group_handle_is_open(C4GroupHandle* handle)
+{
+  return HANDLE_TO_GROUP(handle)->IsOpen();
+}
+
 bool c4_group_handle_is_folder(C4GroupHandle* handle)
 {
 	C4Group *group = HANDLE_TO_GROUP(handle);
 	return group->IsOpen() && !group->IsPacked();
 }
 
+bool c4_group_handle_is_child_group(C4GroupHandle* handle, const char* name, bool* packed)
+{
+  C4Group* group = HANDLE_TO_GROUP(handle);
+  if (group->IsPacked())
+  {
+    // Child groups of packed groups are always packed and flagged in their entry.
+    C4GroupEntry* entry = GroupEntryAccess::GetEntry(group, name);
+    *packed = true;
+    return entry && entry->ChildGroup;
+  }
+  // The engine doesn't track this for entries of folders, so look at the file itself instead of
+  // trying to open every entry as group.
+  StdStrBuf path = group->GetFullName();
+  path.AppendBackslash();
+  path.Append(name);
+  *packed = !DirectoryExists(path.getData());
+  return !*packed || IsPackedGroupFile(path.getData());
+}
+
+bool c4_group_handle_add_from_memory(C4GroupHandle* handle, const char* name, const void* data, size_t size)
+{
+  // The group takes ownership of the copy and frees it after saving.
+  BYTE* buffer = new BYTE[size];
+  memcpy(buffer, data, size);
+  return HANDLE_TO_GROUP(handle)->Add(name, buffer, size, false, true);
+}
+
+bool c4_group_handle_add_from_file(C4GroupHandle* handle, const char* path, const char* name)
+{
+  return HANDLE_TO_GROUP(handle)->Add(path, name);
+}
+
+bool c4_group_handle_delete(C4GroupHandle* handle, const char* wildcard, bool recursive)
+{
+  return HANDLE_TO_GROUP(handle)->Delete(wildcard, recursive);
+}
+
+bool c4_group_handle_save(C4GroupHandle* handle)
+{
+  // Reopen so that the handle stays usable.
+  return HANDLE_TO_GROUP(handle)->Save(true);
+}
+
+bool c4_group_pack_directory(const char* path)
+{
+  return C4Group_PackDirectory(path);
+}
+
+bool c4_group_pack_directory_to(const char* path, const char* to)
+{
+  return C4Group_PackDirectoryTo(path, to);
+}
+
+bool c4_group_unpack_directory(const char* path)
+{
+  return C4Group_UnpackDirectory(path);
+}
+
 } /* extern "C" */
@@ -32,15 +32,28 @@ bool c4_group_handle_open_as_child(C4GroupHandle* handle, C4GroupHandle* mother,
 
 const char* c4_group_handle_get_name(C4GroupHandle* handle);
 char* c4_group_handle_get_full_name(C4GroupHandle* handle);
+void c4_group_handle_free_string(char* string);
 
 void c4_group_handle_reset_search(C4GroupHandle* handle);
 
+char* c4_group_handle_find_next_entry_name(C4GroupHandle* handle, const char* wildcard, size_t* size);
 bool c4_group_handle_find_next_entry(C4GroupHandle* handle, const char* wildcard, size_t* size, char* filename, bool start_at_filename);
 bool c4_group_handle_access_next_entry(C4GroupHandle* handle, const char* wildcard, size_t* size, char* filename, bool start_at_filename);
 bool c4_group_handle_access_entry(C4GroupHandle* handle, const char* wildcard, size_t* size, char* filename, bool needs_to_be_a_group);
 size_t c4_group_handle_accessed_entry_size(C4GroupHandle* handle);
 bool c4_group_handle_read(C4GroupHandle* handle, void* buffer, size_t size);
+bool c4_group_handle_is_open(C4GroupHandle* handle);
 bool c4_group_handle_is_folder(C4GroupHandle* handle);
+bool c4_group_handle_is_child_group(C4GroupHandle* handle, const char* name, bool* packed);
+
+bool c4_group_handle_add_from_memory(C4GroupHandle* handle, const char* name, const void* data, size_t size);
+bool c4_group_handle_add_from_file(C4GroupHandle* handle, const char* path, const char* name);
+bool c4_group_handle_delete(C4GroupHandle* handle, const char* wildcard, bool recursive);
+bool c4_group_handle_save(C4GroupHandle* handle);
+
+bool c4_group_pack_directory(const char* path);
+bool c4_group_pack_directory_to(const char* path, const char* to);
+bool c4_group_unpack_directory(const char* path);
 
 #ifdef __cplusplus
 }
@@ -25,6 +25,7 @@
 #include "lib/StdMeshLoader.h"
 #include "lib/StdColors.h"
 #include "c4group/C4Components.h"
+#include "graphics/Bitmap256.h"
 
 #include "material-handle.h"
 #include "texture-handle.h"
@@ -113,6 +114,53 @@ static int32_t startup_player_count = 1, startup_team_count = 1;
 static int32_t FnGetStartupPlayerCount(C4PropList * _this) { return startup_player_count; }
 static int32_t FnGetStartupTeamCount(C4PropList * _this) { return startup_team_count; }
 
+// Sets the result of GetStartupPlayerCount() for a single map rendering.
+class PlayerCountOverride
+{
+	int32_t previous;
+public:
+	PlayerCountOverride(int32_t count): previous(startup_player_count) { startup_player_count = count; }
+	~PlayerCountOverride() { startup_player_count = previous; }
+};
+
+void SetValue(C4SVal& val, const C4MapgenValue& config)
+{
+	val.Set(config.standard, config.random, config.min, config.max);
+}
+
+void SetLandscape(C4SLandscape& landscape, const C4MapgenLandscape* config)
+{
+	landscape.Default();
+
+	SetValue(landscape.MapWdt, config->map_width);
+	SetValue(landscape.MapHgt, config->map_height);
+	SetValue(landscape.MapZoom, config->map_zoom);
+	landscape.MapPlayerExtend = config->map_player_extend;
+	landscape.ExactLandscape = config->exact_landscape;
+	landscape.BottomOpen = config->bottom_open;
+	landscape.TopOpen = config->top_open;
+	landscape.LeftOpen = config->left_open;
+	landscape.RightOpen = config->right_open;
+	landscape.AutoScanSideOpen = config->auto_scan_side_open;
+	if (config->sky)
+		SCopy(config->sky, landscape.SkyDef, C4MaxDefString);
+	for (int i = 0; i < 6; i++)
+		landscape.SkyDefFade[i] = config->sky_fade[i];
+	SetValue(landscape.Amplitude, config->amplitude);
+	SetValue(landscape.Phase, config->phase);
+	SetValue(landscape.Period, config->period);
+	SetValue(landscape.Random, config->random);
+	SetValue(landscape.LiquidLevel, config->liquid_level);
+	SetValue(landscape.Gravity, config->gravity);
+	if (config->material)
+		SCopy(config->material, landscape.Material, C4M_MaxDefName);
+	if (config->liquid)
+		SCopy(config->liquid, landscape.Liquid, C4M_MaxDefName);
+	landscape.Layers.Clear();
+	for (size_t i = 0; i < config->layer_count; i++)
+		landscape.Layers.Add(config->layer_names[i], config->layer_counts[i]);
+}
+
 bool SaveMap(CSurface8& map, const char* path, C4MaterialMap& material_map, C4TextureMap& texture_map)
 {
 	CStdPalette Palette;
@@ -120,6 +168,25 @@ bool SaveMap(CSurface8& map, const char* path, C4MaterialMap& material_map, C4Te
 	return map.Save(path, &Palette);
 }
 
+// Writes the map as indexed bmp into a buffer, like CSurface8::Save does into a file.
+void MapToBmp(CSurface8& map, std::vector<BYTE>& out, C4MaterialMap& material_map, C4TextureMap& texture_map)
+{
+	CStdPalette Palette;
+	texture_map.StoreMapPalette(&Palette, material_map);
+	C4BMP256Info info;
+	info.Set(map.Wdt, map.Hgt, &Palette);
+	const BYTE* header = reinterpret_cast<const BYTE*>(&info);
+	out.assign(header, header + sizeof(info));
+	// Rows are stored bottom-up, each padded to four bytes.
+	int padding = DWordAligned(map.Wdt) - map.Wdt;
+	for (int y = map.Hgt - 1; y >= 0; y--)
+	{
+		const BYTE* row = map.Bits + map.Pitch * y;
+		out.insert(out.end(), row, row + map.Wdt);
+		out.insert(out.end(), padding, 0);
+	}
+}
+
 }
 
 extern "C" {
@@ -130,6 +197,7 @@ struct _C4MapgenHandle {
 	unsigned int rowstride;
 	std::string error_message, script_output;
 	std::unique_ptr<CSurface8> fg, bg;
+	std::vector<BYTE> bmp;
 };
 
 void c4_mapgen_handle_init_script_engine()
@@ -156,6 +224,7 @@ void c4_mapgen_handle_init_script_engine()
 
 void c4_mapgen_handle_deinit_script_engine()
 {
+	::Definitions.Clear();
 	ClearScriptEngine();
 	system_scripts.clear();
 	startup_player_count = 1;
@@ -164,22 +233,26 @@ void c4_mapgen_handle_deinit_script_engine()
 
 void c4_mapgen_handle_set_map_library(C4GroupHandle* group_handle)
 {
-	::Definitions.Clear();
+	// Overloads a Library_Map loaded before, keeping all other definitions.
+	if(!c4_mapgen_handle_load_definition(group_handle, "Library_Map"))
+		fprintf(stderr, "Failed to load Library_Map script\n");
+}
 
-	C4Def* libmap = new C4Def;
-	libmap->id = C4ID(std::string("Library_Map"));
-	libmap->SetName(libmap->id.ToString());
-	libmap->Category = C4D_StaticBack;
+bool c4_mapgen_handle_load_definition(C4GroupHandle* group_handle, const char* id)
+{
+	C4Def* def = new C4Def;
+	def->id = C4ID(std::string(id));
+	def->SetName(def->id.ToString());
+	def->Category = C4D_StaticBack;
 	FakeSkeletonLoader loader;
-	if(!libmap->Load(*HANDLE_TO_GROUP(group_handle), loader, C4D_Load_Script, nullptr, nullptr))
+	if(!def->Load(*HANDLE_TO_GROUP(group_handle), loader, C4D_Load_Script, nullptr, nullptr))
 	{
-		fprintf(stderr, "Failed to load Library_Map script\n");
-		delete libmap;
-	}
-	else
-	{
-		::Definitions.Add(libmap, false);
+		delete def;
+		return false;
 	}
+	// Definitions loaded later overload earlier ones, as in the engine.
+	::Definitions.Add(def, true);
+	return true;
 }
 
 void c4_mapgen_handle_load_system(C4GroupHandle* group_handle)
@@ -211,8 +284,10 @@ void c4_mapgen_handle_set_startup_team_count(int32_t count)
 	startup_team_count = count;
 }
 
-C4MapgenHandle* c4_mapgen_handle_new_script(const char* filename, const char* source, C4ScenparHandle* scenpar, C4MaterialMapHandle* material_map, C4TextureMapHandle* texture_map, unsigned int map_width, unsigned int map_height)
+C4MapgenHandle* c4_mapgen_handle_new_script(const char* filename, const char* source, C4ScenparHandle* scenpar, C4MaterialMapHandle* material_map, C4TextureMapHandle* texture_map, const C4MapgenLandscape* landscape_config, int32_t player_count)
 {
+	PlayerCountOverride player_count_override(player_count);
+
 	// Re-initialize script engine. Otherwise, we get a warning when the user
 	// changes the value of a constant, since it is defined already from the
 	// previous map rendering.  Note that we do not need to re-load the map library.
@@ -231,11 +306,7 @@ C4MapgenHandle* c4_mapgen_handle_new_script(const char* filename, const char* so
 		// TODO: Could also re-use an existing CSurface8,
 		// saving unnecessary malloc/free between map renderings
 		C4SLandscape landscape;
-		landscape.Default();
-
-		landscape.MapWdt.Set(map_width, 0, map_width, map_width);
-		landscape.MapHgt.Set(map_height, 0, map_height, map_height);
-		landscape.MapPlayerExtend = 0;
+		SetLandscape(landscape, landscape_config);
 
 		ErrorHandler error_handler;
 		::MapScript.LoadData(filename, source, nullptr);
@@ -258,7 +329,7 @@ C4MapgenHandle* c4_mapgen_handle_new_script(const char* filename, const char* so
 			&landscape,
 			HANDLE_TO_TEXTURE_MAP(texture_map),
 			HANDLE_TO_MATERIAL_MAP(material_map),
-			1,
+			player_count,
 			&out_ptr_fg, &out_ptr_bg);
 
 		// Don't show any map if there was a script runtime error
@@ -292,22 +363,20 @@ C4MapgenHandle* c4_mapgen_handle_new_script(const char* filename, const char* so
 	}
 }
 
-C4MapgenHandle* c4_mapgen_handle_new(const char* filename, const char* source, const char* script_path, C4MaterialMapHandle* material_map, C4TextureMapHandle* texture_map, unsigned int map_width, unsigned int map_height)
+C4MapgenHandle* c4_mapgen_handle_new(const char* filename, const char* source, const char* script_path, const char* script_source, C4MaterialMapHandle* material_map, C4TextureMapHandle* texture_map, const C4MapgenLandscape* landscape_config, int32_t player_count)
 {
+	PlayerCountOverride player_count_override(player_count);
+
 	try
 	{
 		C4SLandscape landscape;
-		landscape.Default();
-
-		landscape.MapWdt.Set(map_width, 0, map_width, map_width);
-		landscape.MapHgt.Set(map_height, 0, map_height, map_height);
-		landscape.MapPlayerExtend = 0;
+		SetLandscape(landscape, landscape_config);
 
 		C4MapCreatorS2 mapgen(
 			&landscape,
 			HANDLE_TO_TEXTURE_MAP(texture_map),
 			HANDLE_TO_MATERIAL_MAP(material_map),
-			1
+			player_count
 		);
 
 		C4MCParser parser(&mapgen);
@@ -327,29 +396,37 @@ C4MapgenHandle* c4_mapgen_handle_new(const char* filename, const char* source, c
 			ClearScriptEngine();
 			c4_mapgen_handle_init_script_engine();
 
-			if(script_path == nullptr)
-				throw std::runtime_error("For algo=script overlays to work, save the file first at the location of the Script.c file");
-
-			char dirname[_MAX_PATH]; GetParentPath(script_path, dirname);
-			const char* basename = GetFilename(script_path);
-
-			C4Group File;
-			if(!File.Open(dirname))
+			if(script_source != nullptr)
 			{
-				StdStrBuf error_msg = FormatString("Failed to open directory '%s': %s", dirname, File.GetError());
-				throw std::runtime_error(error_msg.getData());
+				// Read by the caller already, e.g. from a packed scenario.
+				GameScript.LoadData(script_path && *script_path ? script_path : "Script.c", script_source, nullptr);
 			}
-
-			// get scripts
-			File.ResetSearch();
-			if(!File.FindNextEntry(basename, (char*)nullptr))
+			else
 			{
-				StdStrBuf error_msg = FormatString("Failed to load '%s': No such file", script_path);
-				throw std::runtime_error(error_msg.getData());
+				if(script_path == nullptr)
+					throw std::runtime_error("For algo=script overlays to work, save the file first at the location of the Script.c file");
+
+				char dirname[_MAX_PATH]; GetParentPath(script_path, dirname);
+				const char* basename = GetFilename(script_path);
+
+				C4Group File;
+				if(!File.Open(dirname))
+				{
+					StdStrBuf error_msg = FormatString("Failed to open directory '%s': %s", dirname, File.GetError());
+					throw std::runtime_error(error_msg.getData());
+				}
+
+				// get scripts
+				File.ResetSearch();
+				if(!File.FindNextEntry(basename, (char*)nullptr))
+				{
+					StdStrBuf error_msg = FormatString("Failed to load '%s': No such file", script_path);
+					throw std::runtime_error(error_msg.getData());
+				}
+
+				GameScript.Load(File, basename, nullptr, nullptr);
 			}
 
-			GameScript.Load(File, basename, nullptr, nullptr);
-
 			error_handler.CheckErrors();
 
 			// Link script engine (resolve includes/appends, generate code)
@@ -399,6 +476,88 @@ C4MapgenHandle* c4_mapgen_handle_new(const char* filename, const char* source, c
 	}
 }
 
+namespace
+{
+
+// Appends one line per node, indented by two spaces per level, see LandscapeTxt::outline.
+void OutlineNodes(C4MCNode* node, int depth, std::string& out)
+{
+	for(; node; node = node->Next)
+	{
+		out.append(2 * depth, ' ');
+		switch(node->Type())
+		{
+		case MCN_Map: out += "map"; break;
+		case MCN_Overlay: out += "overlay"; break;
+		case MCN_Point: out += "point"; break;
+		default: out += "node"; break;
+		}
+		if(*node->Name)
+		{
+			out += ' ';
+			out += node->Name;
+		}
+		if(node->Type() == MCN_Overlay || node->Type() == MCN_Map)
+		{
+			switch(static_cast<C4MCOverlay*>(node)->Op)
+			{
+			case MCT_OR: out += " |"; break;
+			case MCT_AND: out += " &"; break;
+			case MCT_XOR: out += " ^"; break;
+			default: break;
+			}
+		}
+		out += '\n';
+		OutlineNodes(node->Child0, depth + 1, out);
+	}
+}
+
+char* CopyString(const std::string& str)
+{
+	char* res = static_cast<char*>(malloc(str.size() + 1));
+	memcpy(res, str.c_str(), str.size() + 1);
+	return res;
+}
+
+}
+
+char* c4_mapgen_handle_parse_landscape(const char* filename, const char* source, C4MaterialMapHandle* material_map, C4TextureMapHandle* texture_map, char** error)
+{
+	*error = nullptr;
+	try
+	{
+		C4SLandscape landscape;
+		landscape.Default();
+		C4MapCreatorS2 mapgen(
+			&landscape,
+			HANDLE_TO_TEXTURE_MAP(texture_map),
+			HANDLE_TO_MATERIAL_MAP(material_map),
+			1
+		);
+
+		C4MCParser parser(&mapgen);
+		parser.ParseMemFile(source, filename);
+
+		std::string outline;
+		OutlineNodes(mapgen.Child0, 0, outline);
+		return CopyString(outline);
+	}
+	catch(const C4MCParserErr& err)
+	{
+		*error = CopyString(err.Msg);
+	}
+	catch(const std::exception& ex)
+	{
+		*error = CopyString(ex.what());
+	}
+	return nullptr;
+}
+
+void c4_mapgen_handle_free_string(char* string)
+{
+	free(string);
+}
+
 void c4_mapgen_handle_free(C4MapgenHandle* mapgen)
 {
 	delete mapgen;
@@ -428,6 +587,15 @@ bool c4_mapgen_handle_save_bg(C4MapgenHandle* mapgen, const char* path, C4Materi
 	return SaveMap(*mapgen->bg, path, *matmap, *texmap);
 }
 
+const unsigned char* c4_mapgen_handle_get_map_bmp(C4MapgenHandle* mapgen, bool bg, size_t* size, C4MaterialMapHandle* material_map, C4TextureMapHandle* texture_map)
+{
+	auto matmap = HANDLE_TO_MATERIAL_MAP(material_map);
+	auto texmap = HANDLE_TO_TEXTURE_MAP(texture_map);
+	MapToBmp(bg ? *mapgen->bg : *mapgen->fg, mapgen->bmp, *matmap, *texmap);
+	*size = mapgen->bmp.size();
+	return mapgen->bmp.data();
+}
+
 unsigned int c4_mapgen_handle_get_width(C4MapgenHandle* mapgen)
 {
 	assert(mapgen->fg);
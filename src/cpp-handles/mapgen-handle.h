@@ -27,18 +27,59 @@ extern "C" {
 
 typedef struct _C4MapgenHandle C4MapgenHandle;
 
+// Randomizable value, see C4SVal.
+typedef struct _C4MapgenValue {
+	int32_t standard;
+	int32_t random;
+	int32_t min;
+	int32_t max;
+} C4MapgenValue;
+
+// [Landscape] section of Scenario.txt, see C4SLandscape. Strings may be NULL.
+typedef struct _C4MapgenLandscape {
+	C4MapgenValue map_width;
+	C4MapgenValue map_height;
+	C4MapgenValue map_zoom;
+	int32_t map_player_extend;
+	bool exact_landscape;
+	bool bottom_open;
+	bool top_open;
+	int32_t left_open;
+	int32_t right_open;
+	bool auto_scan_side_open;
+	const char* sky;
+	int32_t sky_fade[6];
+	C4MapgenValue amplitude;
+	C4MapgenValue phase;
+	C4MapgenValue period;
+	C4MapgenValue random;
+	C4MapgenValue liquid_level;
+	C4MapgenValue gravity;
+	const char* material;
+	const char* liquid;
+	const char* const* layer_names;
+	const int32_t* layer_counts;
+	size_t layer_count;
+} C4MapgenLandscape;
+
 void c4_mapgen_handle_init_script_engine();
 void c4_mapgen_handle_deinit_script_engine();
 void c4_mapgen_handle_set_map_library(C4GroupHandle* group_handle);
+bool c4_mapgen_handle_load_definition(C4GroupHandle* group_handle, const char* id);
 void c4_mapgen_handle_load_system(C4GroupHandle* group_handle);
 void c4_mapgen_handle_load_script(const char* filename, const char* source);
 void c4_mapgen_handle_set_startup_player_count(int32_t count);
 void c4_mapgen_handle_set_startup_team_count(int32_t count);
 
-C4MapgenHandle* c4_mapgen_handle_new_script(const char* filename, const char* source, C4ScenparHandle* scenpar, C4MaterialMapHandle* material_map, C4TextureMapHandle* texture_map, unsigned int map_width, unsigned int map_height);
-C4MapgenHandle* c4_mapgen_handle_new(const char* filename, const char* source, const char* script_path, C4MaterialMapHandle* material_map, C4TextureMapHandle* texture_map, unsigned int map_width, unsigned int map_height);
+C4MapgenHandle* c4_mapgen_handle_new_script(const char* filename, const char* source, C4ScenparHandle* scenpar, C4MaterialMapHandle* material_map, C4TextureMapHandle* texture_map, const C4MapgenLandscape* landscape, int32_t player_count);
+C4MapgenHandle* c4_mapgen_handle_new(const char* filename, const char* source, const char* script_path, const char* script_source, C4MaterialMapHandle* material_map, C4TextureMapHandle* texture_map, const C4MapgenLandscape* landscape, int32_t player_count);
 void c4_mapgen_handle_free(C4MapgenHandle* mapgen);
 
+// Parses a Landscape.txt without rendering it. Returns an outline of the node tree with one node
+// per line, or NULL and the message in *error. Free both with c4_mapgen_handle_free_string.
+char* c4_mapgen_handle_parse_landscape(const char* filename, const char* source, C4MaterialMapHandle* material_map, C4TextureMapHandle* texture_map, char** error);
+void c4_mapgen_handle_free_string(char* string);
+
 // Get map as byte array.
 const unsigned char* c4_mapgen_handle_get_map(C4MapgenHandle* mapgen);
 const unsigned char* c4_mapgen_handle_get_bg(C4MapgenHandle* mapgen);
@@ -46,6 +87,8 @@ const unsigned char* c4_mapgen_handle_get_bg(C4MapgenHandle* mapgen);
 // Save map as bmp.
 bool c4_mapgen_handle_save_map(C4MapgenHandle* mapgen, const char* path, C4MaterialMapHandle* material_map, C4TextureMapHandle* texture_map);
 bool c4_mapgen_handle_save_bg(C4MapgenHandle* mapgen, const char* path, C4MaterialMapHandle* material_map, C4TextureMapHandle* texture_map);
+/* Returns the map or bg as indexed bmp. The data is valid until the next call. */
+const unsigned char* c4_mapgen_handle_get_map_bmp(C4MapgenHandle* mapgen, bool bg, size_t* size, C4MaterialMapHandle* material_map, C4TextureMapHandle* texture_map);
 
 unsigned int c4_mapgen_handle_get_width(C4MapgenHandle* mapgen);
 unsigned int c4_mapgen_handle_get_height(C4MapgenHandle* mapgen);
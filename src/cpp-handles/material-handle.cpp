@@ -82,6 +82,13 @@ C4MaterialHandle* c4_material_map_handle_get_material(C4MaterialMapHandle* mater
   return MATERIAL_TO_HANDLE(&HANDLE_TO_MATERIAL_MAP(material_map)->Map[index]);
 }
 
+int32_t c4_material_map_handle_get_index(C4MaterialMapHandle* material_map, const char* name)
+{
+  // Case-insensitive like material lookups in the engine.
+  int32_t index = HANDLE_TO_MATERIAL_MAP(material_map)->Get(name);
+  return index == MNone ? -1 : index;
+}
+
 const char* c4_material_handle_get_name(C4MaterialHandle* material)
 {
   return HANDLE_TO_MATERIAL(material)->Name;
@@ -91,4 +98,24 @@ const char* c4_material_handle_get_texture_overlay(C4MaterialHandle* material)
   return HANDLE_TO_MATERIAL(material)->sTextureOverlay.getData();
 }
 
+void c4_material_handle_get_properties(C4MaterialHandle* material, C4MaterialProperties* properties)
+{
+  C4Material* mat = HANDLE_TO_MATERIAL(material);
+  properties->density = mat->Density;
+  properties->friction = mat->Friction;
+  properties->dig_free = mat->DigFree;
+  properties->blast_free = mat->BlastFree;
+  properties->instable = mat->Instable;
+  properties->placement = mat->Placement;
+  properties->incendiary = mat->Incendiary;
+  properties->extinguisher = mat->Extinguisher;
+  properties->light_angle = mat->LightAngle;
+  for(int i = 0; i < 3; ++i)
+  {
+    properties->light_emit[i] = mat->LightEmit[i];
+    properties->light_spot[i] = mat->LightSpot[i];
+  }
+  properties->min_shape_overlap = mat->MinShapeOverlap;
+}
+
 } /* extern "C" */
@@ -27,6 +27,22 @@ extern "C" {
 typedef struct _C4MaterialHandle C4MaterialHandle;
 typedef struct _C4MaterialMapHandle C4MaterialMapHandle;
 
+// Properties of a C4Material relevant for the map generator, see C4MaterialCore.
+typedef struct _C4MaterialProperties {
+  int32_t density;
+  int32_t friction;
+  int32_t dig_free;
+  int32_t blast_free;
+  int32_t instable;
+  int32_t placement;
+  int32_t incendiary;
+  int32_t extinguisher;
+  int32_t light_angle;
+  int32_t light_emit[3];
+  int32_t light_spot[3];
+  int32_t min_shape_overlap;
+} C4MaterialProperties;
+
 C4MaterialMapHandle* c4_material_map_handle_new(void);
 void c4_material_map_handle_free(C4MaterialMapHandle* material_map);
 
@@ -35,9 +51,12 @@ void c4_material_map_crossmap_materials(C4MaterialMapHandle* material_map, C4Tex
 
 unsigned int c4_material_map_handle_get_num(C4MaterialMapHandle* material_map);
 C4MaterialHandle* c4_material_map_handle_get_material(C4MaterialMapHandle* material_map, unsigned int index);
+// Returns -1 if there is no material with this name.
+int32_t c4_material_map_handle_get_index(C4MaterialMapHandle* material_map, const char* name);
 
 const char* c4_material_handle_get_name(C4MaterialHandle* material);
 const char* c4_material_handle_get_texture_overlay(C4MaterialHandle* material);
+void c4_material_handle_get_properties(C4MaterialHandle* material, C4MaterialProperties* properties);
 
 #ifdef __cplusplus
 }
@@ -24,4 +24,9 @@ void c4_random_handle_seed(unsigned int seed)
   FixedRandom(seed);
 }
 
+unsigned int c4_random_handle_next_seed(void)
+{
+  return Random();
+}
+
 } // extern "C"
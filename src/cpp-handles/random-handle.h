@@ -21,6 +21,7 @@ extern "C" {
 #endif
 
 void c4_random_handle_seed(unsigned int seed);
+unsigned int c4_random_handle_next_seed(void);
 
 #ifdef __cplusplus
 }
@@ -51,5 +51,70 @@ void c4_scenpar_handle_set_value(C4ScenparHandle* handle, const char* id, int32_
 	handle->parameters.SetValue(id, value, only_if_larger);
 }
 
+size_t c4_scenpar_handle_get_num_defs(C4ScenparHandle* handle)
+{
+	size_t num = 0;
+	while (handle->parameter_defs.GetParameterDefByIndex(num))
+		num++;
+	return num;
+}
+
+#define GET_DEF(handle, index) (handle->parameter_defs.GetParameterDefByIndex(index))
+
+const char* c4_scenpar_handle_get_def_id(C4ScenparHandle* handle, size_t index)
+{
+	return GET_DEF(handle, index)->GetID();
+}
+
+const char* c4_scenpar_handle_get_def_name(C4ScenparHandle* handle, size_t index)
+{
+	return GET_DEF(handle, index)->GetName();
+}
+
+const char* c4_scenpar_handle_get_def_description(C4ScenparHandle* handle, size_t index)
+{
+	return GET_DEF(handle, index)->GetDescription();
+}
+
+int32_t c4_scenpar_handle_get_def_type(C4ScenparHandle* handle, size_t index)
+{
+	return GET_DEF(handle, index)->GetType();
+}
+
+int32_t c4_scenpar_handle_get_def_default(C4ScenparHandle* handle, size_t index)
+{
+	return GET_DEF(handle, index)->GetDefault();
+}
+
+bool c4_scenpar_handle_get_def_is_achievement(C4ScenparHandle* handle, size_t index)
+{
+	return GET_DEF(handle, index)->IsAchievement();
+}
+
+size_t c4_scenpar_handle_get_def_num_options(C4ScenparHandle* handle, size_t index)
+{
+	size_t num = 0;
+	while (GET_DEF(handle, index)->GetOptionByIndex(num))
+		num++;
+	return num;
+}
+
+int32_t c4_scenpar_handle_get_def_option_value(C4ScenparHandle* handle, size_t index, size_t option)
+{
+	return GET_DEF(handle, index)->GetOptionByIndex(option)->Value;
+}
+
+const char* c4_scenpar_handle_get_def_option_name(C4ScenparHandle* handle, size_t index, size_t option)
+{
+	return GET_DEF(handle, index)->GetOptionByIndex(option)->Name.getData();
+}
+
+const char* c4_scenpar_handle_get_def_option_description(C4ScenparHandle* handle, size_t index, size_t option)
+{
+	return GET_DEF(handle, index)->GetOptionByIndex(option)->Description.getData();
+}
+
+#undef GET_DEF
+
 
 } /* extern "C" */
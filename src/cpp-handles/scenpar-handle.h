@@ -32,6 +32,19 @@ bool c4_scenpar_handle_load(C4ScenparHandle* handle, C4GroupHandle* group);
 int32_t c4_scenpar_handle_get_value_by_id(C4ScenparHandle* handle, const char* id, int32_t default_value);
 void c4_scenpar_handle_set_value(C4ScenparHandle* handle, const char* id, int32_t value, bool only_if_larger);
 
+// Parameter definitions. Strings may be NULL if not set.
+size_t c4_scenpar_handle_get_num_defs(C4ScenparHandle* handle);
+const char* c4_scenpar_handle_get_def_id(C4ScenparHandle* handle, size_t index);
+const char* c4_scenpar_handle_get_def_name(C4ScenparHandle* handle, size_t index);
+const char* c4_scenpar_handle_get_def_description(C4ScenparHandle* handle, size_t index);
+int32_t c4_scenpar_handle_get_def_type(C4ScenparHandle* handle, size_t index);
+int32_t c4_scenpar_handle_get_def_default(C4ScenparHandle* handle, size_t index);
+bool c4_scenpar_handle_get_def_is_achievement(C4ScenparHandle* handle, size_t index);
+size_t c4_scenpar_handle_get_def_num_options(C4ScenparHandle* handle, size_t index);
+int32_t c4_scenpar_handle_get_def_option_value(C4ScenparHandle* handle, size_t index, size_t option);
+const char* c4_scenpar_handle_get_def_option_name(C4ScenparHandle* handle, size_t index, size_t option);
+const char* c4_scenpar_handle_get_def_option_description(C4ScenparHandle* handle, size_t index, size_t option);
+
 #ifdef __cplusplus
 }
 #endif
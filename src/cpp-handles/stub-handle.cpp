@@ -31,6 +31,10 @@
 #include "landscape/C4TextureShape.h"
 #include "landscape/C4Sky.h"
 #include "graphics/C4Draw.h"
+#include "c4group/C4Group.h"
+#include "lib/C4Log.h"
+
+#include <cstring>
 
 /* This file implements stubs for the parts of the engine that are not used
  * by mape. */
@@ -86,7 +90,30 @@ C4PXSSystem::C4PXSSystem() {}
 C4PXSSystem::~C4PXSSystem() {}
 bool C4PXSSystem::Create(int, C4Real, C4Real, C4Real, C4Real) { return false; }
 
-bool C4TextureShape::Load(C4Group &group, const char *filename, int32_t base_tex_wdt, int32_t base_tex_hgt) { return true; }
+// Texture shapes are decoded and drawn by TextureMap::load_textures on the Rust side, as libpng
+// isn't linked. Loading here reads the size from the PNG header and checks it like the engine.
+bool C4TextureShape::Load(C4Group &group, const char *filename, int32_t base_tex_wdt, int32_t base_tex_hgt)
+{
+	StdBuf png_data;
+	if (!group.LoadEntry(filename, &png_data)) return false;
+	// The signature is followed by the IHDR chunk with width and height as big-endian integers.
+	static const BYTE signature[] = { 0x89, 'P', 'N', 'G', '\r', '\n', 0x1a, '\n' };
+	const BYTE *data = static_cast<const BYTE *>(png_data.getData());
+	if (png_data.getSize() < 24 || memcmp(data, signature, sizeof(signature)) || memcmp(data + 12, "IHDR", 4))
+	{
+		LogF("ERROR: Material shape texture %s is not a PNG file. Not loading.", filename);
+		return false;
+	}
+	auto read_size = [data](size_t pos) { return uint32_t(data[pos]) << 24 | uint32_t(data[pos + 1]) << 16 | uint32_t(data[pos + 2]) << 8 | uint32_t(data[pos + 3]); };
+	uint32_t wdt = read_size(16), hgt = read_size(20);
+	uint32_t zoom = base_tex_wdt > 0 ? wdt / base_tex_wdt : 0;
+	if (!zoom || base_tex_hgt <= 0 || base_tex_wdt * zoom != wdt || base_tex_hgt * zoom != hgt)
+	{
+		LogF("ERROR: Material shape texture %s size (%u,%u) not a multiple of associated texture size (%d,%d). Not loading.", filename, wdt, hgt, int(base_tex_wdt), int(base_tex_hgt));
+		return false;
+	}
+	return true;
+}
 
 C4Shader::C4Shader() {}
 C4Shader::~C4Shader() {}
@@ -1,10 +1,10 @@
-use crate::{Group, MaterialMap, TextureMap, Scenpar, MapGen, MapGenHandle};
+use crate::{AlgoScript, Group, MaterialMap, TextureMap, Scenpar, MapGen, MapGenHandle, RenderSettings, AssetBundle, AssetConfig, AssetReport, Scenario, Landscape, ScenarioValue, ContactSheet};
 use crate::errors::*;
 
 use std::io::prelude::*;
 use std::io;
 use std::fs::File;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use error_chain::bail;
 use regex::bytes::Regex;
@@ -32,72 +32,139 @@ impl Easy {
     /// Sets the base path for loading materials and scripts.
     ///
     /// The path has to be the OpenClonk base directory (containing Material.ocg, System.ocg, Objects.ocd) or a subdirectory.
+    /// See `AssetConfig::discover` for details on how the files are found.
     pub fn set_base_path<P: AsRef<Path>>(&mut self, path: P) -> Result<()> {
-        let mut path = path.as_ref().canonicalize()?;
-        let mut base_group = None;
-        let mut overloaded_groups = Vec::new();
-        let mut search_for_overload = false;
-        loop {
-            // Search closest Material.ocg as well as the base directory with System.ocg and
-            // Objects.ocd.
-            if base_group.is_none() || search_for_overload {
-                let material_ocg = path.join("Material.ocg");
-                if material_ocg.exists() {
-                    let material_group = Group::open(material_ocg.to_str().unwrap(), false)?;
-                    let result = self.texture_map.load_map(&material_group)?;
-                    search_for_overload = result.overload_materials || result.overload_textures;
-                    if base_group.is_none() {
-                        base_group = Some(material_group);
-                    } else {
-                        overloaded_groups.push(material_group);
-                    }
-                }
-            }
+        let config = AssetConfig::discover(path)?;
+        self.load_assets(&config)?;
+        Ok(())
+    }
 
-            let system_ocg = path.join("System.ocg");
-            let objects_ocd = path.join("Objects.ocd");
-            if system_ocg.exists() && objects_ocd.exists() {
-                // Done, we found the root.
-                let root_group = Group::open(path.to_str().unwrap(), false)?;
-                self.mapgen.set_root_group(&root_group)?;
-                // Load the top-level System.ocg
-                self.load_system(&system_ocg)?;
-                break;
-            }
+    /// Loads materials, textures and scripts as specified in the configuration.
+    ///
+    /// Returns a report of what was loaded from where.
+    pub fn load_assets(&mut self, config: &AssetConfig) -> Result<AssetReport> {
+        let mut report = AssetReport::default();
 
-            if !path.pop() {
-                bail!("couldn't find base path");
-            }
+        if let Some(map_library) = config.map_library_path() {
+            let group = open_group(map_library)?;
+            self.mapgen.set_map_library(&group)?;
+            report.map_library = Some(map_library.clone());
+        } else if let Some(objects) = config.objects_path() {
+            let objects_group = open_group(objects)?;
+            let libraries = Group::open_as_child(&objects_group, "Libraries.ocd", false, false)?;
+            let map = Group::open_as_child(&libraries, "Map.ocd", false, false)?;
+            self.mapgen.set_map_library(&map)?;
+            report.map_library = Some(objects.join("Libraries.ocd").join("Map.ocd"));
+        }
+
+        for system in config.system_paths() {
+            self.load_system(system)?;
+            report.systems.push(system.clone());
+        }
+
+        for definition in config.definition_paths() {
+            let mut group = open_group(definition)?;
+            self.load_definition_group(&mut group, definition, &mut report.definitions)?;
+        }
+
+        // Groups are given in overload order, but the first loaded material or texture wins.
+        let material_paths: Vec<&PathBuf> = config.material_paths().iter().rev().collect();
+        if material_paths.is_empty() {
+            bail!("couldn't find Material.ocg");
+        }
+        let mut groups = Vec::new();
+        for path in &material_paths {
+            let group = open_group(path)?;
+            self.texture_map.load_map(&group)?;
+            groups.push(group);
         }
-        let mut base_group = match base_group {
-            Some(g) => g,
-            None => bail!("couldn't find Material.ocg")
-        };
         let mut mat_sum = 0;
-        mat_sum += self.material_map.load(&mut base_group)?;
-        for mut overloaded_group in &mut overloaded_groups {
-            mat_sum += self.material_map.load(&mut overloaded_group)?;
+        for (group, path) in groups.iter().zip(&material_paths) {
+            let num = self.material_map.load(group)?;
+            report.materials.push(((*path).clone(), num));
+            mat_sum += num;
         }
         if mat_sum == 0 {
             bail!(ErrorKind::NothingLoaded);
         }
-        base_group.rewind();
-        self.texture_map.load_textures(&mut base_group)?;
-        for mut overloaded_group in &mut overloaded_groups {
-            self.texture_map.load_textures(&mut overloaded_group)?;
+        for (group, path) in groups.iter_mut().zip(&material_paths) {
+            let before = self.texture_map.texture_table.len();
+            self.texture_map.load_textures(group)?;
+            report.textures.push(((*path).clone(), self.texture_map.texture_table.len() - before));
         }
         // Load the texture map a second time, now with textures.
-        let result = self.texture_map.load_map(&mut base_group)?;
+        let result = self.texture_map.load_map(&groups[0])?;
         if result.num_loaded == 0 {
             bail!(ErrorKind::NothingLoaded);
         }
+        report.texture_map = Some(((*material_paths[0]).clone(), result.num_loaded));
         self.material_map.set_default_textures(&self.texture_map);
 
+        // Report in overload order as well.
+        report.materials.reverse();
+        report.textures.reverse();
+
+        Ok(report)
+    }
+
+    /// Loads materials and scripts from an in-memory asset bundle.
+    ///
+    /// The bundle has to contain Material.ocg at its root, System.ocg and Objects.ocd are loaded
+    /// if it contains them as well. Nothing outside of the bundle is used. The bundle is not
+    /// needed anymore after loading and can be dropped.
+    pub fn set_asset_bundle(&mut self, bundle: &AssetBundle) -> Result<()> {
+        let mut config = AssetConfig::new();
+        config.material(bundle.path().join("Material.ocg"));
+        let system = bundle.path().join("System.ocg");
+        if system.exists() {
+            config.system(system);
+        }
+        let objects = bundle.path().join("Objects.ocd");
+        if objects.exists() {
+            config.objects(objects);
+        }
+        self.load_assets(&config)?;
+        Ok(())
+    }
+
+    /// Loads the scripts of all definitions in a .ocd group, including nested definitions.
+    ///
+    /// Returns the IDs of the loaded definitions in load order.
+    pub fn load_definitions<P: AsRef<Path>>(&mut self, path: P) -> Result<Vec<String>> {
+        let path = path.as_ref();
+        let mut group = open_group(path)?;
+        let mut loaded = Vec::new();
+        self.load_definition_group(&mut group, path, &mut loaded)?;
+        Ok(loaded.into_iter().map(|(_, id)| id).collect())
+    }
+
+    fn load_definition_group(&mut self, group: &mut Group, path: &Path, loaded: &mut Vec<(PathBuf, String)>) -> Result<()> {
+        // Plain .ocd groups may just contain other definitions.
+        if let Ok(defcore) = group.load_entry("DefCore.txt") {
+            let re = Regex::new(r"(?m)^\s*id\s*=\s*(\w+)").unwrap();
+            match re.captures(&defcore) {
+                Some(cap) => {
+                    let id = String::from_utf8_lossy(&cap[1]).into_owned();
+                    self.mapgen.load_definition(group, &id)?;
+                    loaded.push((path.to_owned(), id));
+                }
+                None => bail!("no definition ID in {}", path.join("DefCore.txt").display()),
+            }
+        }
+        // As in the engine, nested definitions are loaded after their parent.
+        for child in group.iter_entries("*.ocd") {
+            let child = child?;
+            if !child.is_group {
+                continue;
+            }
+            let mut child_group = Group::open_as_child(group, &child.raw_name, false, false)?;
+            self.load_definition_group(&mut child_group, &path.join(&child.name), loaded)?;
+        }
         Ok(())
     }
 
     fn load_system(&mut self, path: &Path) -> Result<()> {
-        let system_group = Group::open(path.to_str().unwrap(), false)?;
+        let system_group = open_group(path)?;
         // Try to load player controls to generate CON_ constants.
         if let Ok(data) = system_group.load_entry("PlayerControls.txt") {
             let re = Regex::new(r"(?m)^\s*Identifier=(\w+)").unwrap();
@@ -112,6 +179,64 @@ impl Easy {
         self.mapgen.load_system(&system_group)
     }
 
+    /// Renders the map of a scenario (.ocs folder or packed group).
+    ///
+    /// Map size and other settings are taken from the `[Landscape]` section of the scenario's
+    /// Scenario.txt. See `Scenario::load` for the files used. Note that materials and scripts
+    /// have to be loaded before, e.g. with `set_base_path` on the scenario.
+    pub fn render_scenario<P: AsRef<Path>>(&self, path: P) -> Result<MapGenHandle<'_>> {
+        let scenario = Scenario::load(path)?;
+        self.render_loaded_scenario(&scenario)
+    }
+
+    /// Like `render_scenario`, for a scenario loaded before, e.g. to change its parameters.
+    pub fn render_loaded_scenario(&self, scenario: &Scenario) -> Result<MapGenHandle<'_>> {
+        let settings = RenderSettings {
+            landscape: scenario.landscape().clone(),
+            players: self.mapgen.startup_player_count(),
+            seed: None,
+        };
+        let filename = scenario.map_filename();
+        let filename = filename.to_str().unwrap();
+        match scenario.map_type() {
+            MapType::MapC => self.mapgen.render_script(filename,
+                                                       scenario.map_source(),
+                                                       scenario.scenpar(),
+                                                       &self.material_map,
+                                                       &self.texture_map,
+                                                       &settings),
+            MapType::LandscapeTxt => self.mapgen.render_landscape(filename,
+                                                                  scenario.map_source(),
+                                                                  &AlgoScript {
+                                                                      path: scenario.script_path().to_str().unwrap(),
+                                                                      source: scenario.script(),
+                                                                  },
+                                                                  &self.material_map,
+                                                                  &self.texture_map,
+                                                                  &settings),
+        }
+    }
+
+    /// Entry point for rendering the map of a loaded scenario with further settings, e.g. for
+    /// `RenderConfig::sweep`.
+    ///
+    /// The configuration is set up like `render_loaded_scenario` renders the scenario.
+    pub fn build_scenario<'a>(&'a self, scenario: &'a Scenario) -> RenderConfig<'a> {
+        let mut cfg = self.build();
+        cfg.map_type(scenario.map_type())
+           .filename(scenario.map_filename().to_str().unwrap())
+           .source(scenario.map_source())
+           .algo_script_path(scenario.script_path().to_str().unwrap())
+           .landscape(scenario.landscape());
+        if let Some(script) = scenario.script() {
+            cfg.algo_script(script);
+        }
+        if let Some(scenpar) = scenario.scenpar() {
+            cfg.scenpar(scenpar);
+        }
+        cfg
+    }
+
     /// Entry point for rendering the map.
     pub fn build(&self) -> RenderConfig {
         RenderConfig {
@@ -122,7 +247,14 @@ impl Easy {
             width: 200,
             height: 200,
             algo_script_path: None,
+            algo_script: None,
             scenpar: None,
+            landscape: None,
+            map_width: None,
+            map_height: None,
+            players: None,
+            map_player_extend: None,
+            seed: None,
         }
     }
 }
@@ -142,7 +274,14 @@ pub struct RenderConfig<'a> {
     width: u32,
     height: u32,
     algo_script_path: Option<String>,
+    algo_script: Option<String>,
     scenpar: Option<&'a Scenpar>,
+    landscape: Option<Landscape>,
+    map_width: Option<ScenarioValue>,
+    map_height: Option<ScenarioValue>,
+    players: Option<i32>,
+    map_player_extend: Option<bool>,
+    seed: Option<u32>,
 }
 
 impl<'a> RenderConfig<'a> {
@@ -182,14 +321,79 @@ impl<'a> RenderConfig<'a> {
         self
     }
 
+    /// Sets the script source for Algo=Script Landscape.txt maps, instead of loading it from
+    /// `algo_script_path`. The path is still used in error messages.
+    pub fn algo_script<'b>(&'b mut self, algo_script: &str) -> &'b mut RenderConfig<'a> {
+        self.algo_script = Some(algo_script.into());
+        self
+    }
+
     /// Sets scenario parameters to load for script Map.c.
     pub fn scenpar<'b>(&'b mut self, scenpar: &'a Scenpar) -> &'b mut RenderConfig<'a> {
         self.scenpar = Some(scenpar);
         self
     }
 
+    /// Sets the scenario's landscape settings, e.g. from Scenario.txt.
+    ///
+    /// This replaces the map size set with `width` and `height`.
+    pub fn landscape<'b>(&'b mut self, landscape: &Landscape) -> &'b mut RenderConfig<'a> {
+        self.landscape = Some(landscape.clone());
+        self
+    }
+
+    /// Sets the map width with random deviation, like MapWidth in Scenario.txt.
+    ///
+    /// The engine picks the width from `standard ± random` using the RNG seeded for the render,
+    /// clamped to `[min, max]`. Overrides `width` and the setting from `landscape`.
+    pub fn map_width<'b>(&'b mut self, map_width: ScenarioValue) -> &'b mut RenderConfig<'a> {
+        self.map_width = Some(map_width);
+        self
+    }
+
+    /// Sets the map height with random deviation, like MapHeight in Scenario.txt.
+    ///
+    /// See `map_width`.
+    pub fn map_height<'b>(&'b mut self, map_height: ScenarioValue) -> &'b mut RenderConfig<'a> {
+        self.map_height = Some(map_height);
+        self
+    }
+
+    /// Sets the number of players for this render.
+    ///
+    /// This is the result of GetStartupPlayerCount() in `Map.c` and determines the map width if
+    /// `map_player_extend` is enabled. Defaults to the count set with
+    /// `MapGen::set_startup_player_count`.
+    pub fn players<'b>(&'b mut self, players: i32) -> &'b mut RenderConfig<'a> {
+        self.players = Some(players);
+        self
+    }
+
+    /// Makes the map grow in width with the number of players, like MapPlayerExtend in
+    /// Scenario.txt. The width is multiplied by the player count, up to four players.
+    ///
+    /// Overrides the setting from `landscape`.
+    pub fn map_player_extend<'b>(&'b mut self, map_player_extend: bool) -> &'b mut RenderConfig<'a> {
+        self.map_player_extend = Some(map_player_extend);
+        self
+    }
+
+    /// Sets the RNG seed for this render.
+    ///
+    /// The RNG is seeded right before rendering, so rendering again gives the same map. Without a
+    /// seed, each render draws a new one from the global RNG. `MapGenHandle::seed` returns the
+    /// seed used either way.
+    pub fn seed<'b>(&'b mut self, seed: u32) -> &'b mut RenderConfig<'a> {
+        self.seed = Some(seed);
+        self
+    }
+
     /// Renders the map!
-    pub fn render(&self) -> Result<MapGenHandle> {
+    pub fn render(&self) -> Result<MapGenHandle<'a>> {
+        self.render_with_seed(self.seed)
+    }
+
+    fn render_with_seed(&self, seed: Option<u32>) -> Result<MapGenHandle<'a>> {
         let map_type = match self.map_type {
             Some(t) => t,
             None => self.autodetect_map_type()?
@@ -205,18 +409,62 @@ impl<'a> RenderConfig<'a> {
                     }
         };
         let algo_script_path = || self.algo_script_path.as_ref().map(|f| f.as_str()).unwrap_or("");
+        let mut landscape = match self.landscape {
+            Some(ref landscape) => landscape.clone(),
+            None => Landscape::with_map_size(self.width, self.height),
+        };
+        if let Some(map_width) = self.map_width {
+            landscape.map_width = map_width;
+        }
+        if let Some(map_height) = self.map_height {
+            landscape.map_height = map_height;
+        }
+        if let Some(map_player_extend) = self.map_player_extend {
+            landscape.map_player_extend = map_player_extend;
+        }
+        let settings = RenderSettings {
+            landscape,
+            players: self.players.unwrap_or_else(|| self.easy.mapgen.startup_player_count()),
+            seed,
+        };
         match map_type {
             MapType::MapC => self.easy.mapgen.render_script(filename,
                                                             &source,
                                                             self.scenpar.clone(),
                                                             &self.easy.material_map,
                                                             &self.easy.texture_map,
-                                                            self.width,
-                                                            self.height),
-            MapType::LandscapeTxt => self.easy.mapgen.render_landscape(filename, &source, algo_script_path(), &self.easy.material_map, &self.easy.texture_map, self.width, self.height),
+                                                            &settings),
+            MapType::LandscapeTxt => {
+                let script = AlgoScript { path: algo_script_path(), source: self.algo_script.as_deref() };
+                self.easy.mapgen.render_landscape(filename, &source, &script, &self.easy.material_map, &self.easy.texture_map, &settings)
+            },
         }
     }
 
+    /// Renders the map once for each seed and tiles the maps into a contact sheet labelled with
+    /// their seeds.
+    ///
+    /// Maps which fail to render get a placeholder tile. Overrides the seed set with `seed`.
+    pub fn sweep<I: IntoIterator<Item = u32>>(&self, seeds: I) -> Sweep {
+        let mut sweep = Sweep { sheet: ContactSheet::new(), maps: Vec::new() };
+        for seed in seeds {
+            let label = seed.to_string();
+            let stats = self.render_with_seed(Some(seed)).map(|map_handle| {
+                sweep.sheet.add(map_handle.map_as_image(), &label);
+                MapStats {
+                    width: map_handle.width(),
+                    height: map_handle.height(),
+                    materials: map_handle.material_counts(),
+                }
+            });
+            if stats.is_err() {
+                sweep.sheet.add_placeholder(&label);
+            }
+            sweep.maps.push((seed, stats));
+        }
+        sweep
+    }
+
     fn autodetect_map_type(&self) -> Result<MapType> {
         match self.filename {
             Some(ref filename) => match filename.rsplit(".").next() {
@@ -229,6 +477,28 @@ impl<'a> RenderConfig<'a> {
     }
 }
 
+/// Maps rendered for many seeds, see `RenderConfig::sweep`.
+pub struct Sweep {
+    /// The maps labelled with their seed, in the same order as `maps`.
+    pub sheet: ContactSheet,
+    /// Statistics of each seed's map, or why rendering failed.
+    pub maps: Vec<(u32, Result<MapStats>)>,
+}
+
+/// Size and material usage of a rendered map.
+#[derive(Clone, Debug)]
+pub struct MapStats {
+    pub width: u32,
+    pub height: u32,
+    /// Foreground pixels per material, see `MapGenHandle::material_counts`.
+    pub materials: Vec<(String, u32)>,
+}
+
+fn open_group(path: &Path) -> Result<Group> {
+    Group::open(path.to_str().unwrap(), false)
+        .chain_err(|| format!("couldn't open {}", path.display()))
+}
+
 fn read_file(path: &str) -> io::Result<String> {
     let mut contents = String::new();
     File::open(path)?.read_to_string(&mut contents)?;
@@ -249,3 +519,79 @@ pub fn load_scenpar<P: AsRef<Path>>(path: P) -> Result<Scenpar> {
         bail!(ErrorKind::NoParameterDefs)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{Easy, MapType};
+    use crate::{AssetBundle, MaterialProperties, MaterialSet};
+    use crate::landscape_txt::LandscapeTxt;
+
+    use image::{DynamicImage, Rgb, RgbImage};
+
+    use std::sync::{Mutex, MutexGuard};
+
+    /// The generator has global state, so tests have to take turns.
+    static ENGINE: Mutex<()> = Mutex::new(());
+
+    /// Returns a generator with only Earth-earth in its texture map, and the lock to hold while
+    /// using it.
+    fn earth_only() -> (MutexGuard<'static, ()>, Easy) {
+        let lock = ENGINE.lock().unwrap_or_else(|e| e.into_inner());
+        let mut set = MaterialSet::new();
+        set.material("Earth", MaterialProperties { density: 50, dig_free: true, ..Default::default() })
+           .texture("earth", DynamicImage::ImageRgb8(RgbImage::from_pixel(4, 4, Rgb([120, 80, 40]))))
+           .entry("Earth", "earth");
+        let mut bundle = AssetBundle::new().unwrap();
+        set.write_to(&mut bundle).unwrap();
+
+        let mut easy = Easy::new().unwrap();
+        easy.set_asset_bundle(&bundle).unwrap();
+        (lock, easy)
+    }
+
+    #[test]
+    fn render_from_bundle() {
+        let (_lock, easy) = earth_only();
+        let map = easy.build()
+            .map_type(MapType::LandscapeTxt)
+            .source("map Main { overlay { mat=Earth; tex=earth; y=50; }; };")
+            .width(20)
+            .height(10)
+            .render()
+            .unwrap();
+        assert_eq!((map.width(), map.height()), (20, 10));
+        // Sky above, Earth-earth (texture map index 1) below.
+        let indices = map.map_indices();
+        assert_eq!(indices[0], 0);
+        assert_eq!(indices[indices.len() - 1], 1);
+    }
+
+    #[test]
+    fn landscape_txt_like_engine() {
+        let (_lock, easy) = earth_only();
+        let parse = |source| easy.mapgen.parse_landscape("Landscape.txt", source, &easy.material_map, &easy.texture_map);
+        let valid = [
+            "map Main { overlay { mat=Earth; tex=earth; y=50; }; };",
+            "overlay Caves { algo=rndchecker; a=4; zoomX=-50; mat=Earth; tex=earth; };\n\
+             map { overlay { mat=Earth; tex=earth; y=70%; Caves { }; point { x=50; y=50; }; } | overlay { mat=Earth; }; };",
+            "overlay A { overlay B { point P { }; }; };\nmap { A X { overlay { }; }; };",
+            "// Comment\nmap /* x */ { overlay { } ^ overlay { } & overlay { }; };",
+        ];
+        for source in &valid {
+            let outline = LandscapeTxt::parse(source).unwrap().outline().unwrap();
+            assert_eq!(parse(source).unwrap(), outline, "{}", source);
+        }
+        let invalid = [
+            "map { x=5--3; };",
+            "map { x=5-; };",
+            "map { mat=Earth }",
+            "map { overlay { } - overlay { }; };",
+            "map { Caves { }; };",
+            "overlay Caves { Caves { }; };",
+        ];
+        for source in &invalid {
+            assert!(LandscapeTxt::parse(source).is_err(), "{}", source);
+            assert!(parse(source).is_err(), "{}", source);
+        }
+    }
+}
@@ -36,9 +36,19 @@ extern "C" {
     pub fn c4_group_handle_get_full_name(handle: *mut C4GroupHandle)
      -> *mut ::std::os::raw::c_char;
 }
+extern "C" {
+    pub fn c4_group_handle_free_string(string: *mut ::std::os::raw::c_char);
+}
 extern "C" {
     pub fn c4_group_handle_reset_search(handle: *mut C4GroupHandle);
 }
+extern "C" {
+    pub fn c4_group_handle_find_next_entry_name(handle: *mut C4GroupHandle,
+                                                wildcard:
+                                                    *const ::std::os::raw::c_char,
+                                                size: *mut usize)
+     -> *mut ::std::os::raw::c_char;
+}
 extern "C" {
     pub fn c4_group_handle_find_next_entry(handle: *mut C4GroupHandle,
                                            wildcard:
@@ -74,13 +84,91 @@ extern "C" {
                                 buffer: *mut ::std::os::raw::c_void,
                                 size: usize) -> bool;
 }
+extern "C" {
+    pub fn c4_group_handle_is_open(handle: *mut C4GroupHandle) -> bool;
+}
 extern "C" {
     pub fn c4_group_handle_is_folder(handle: *mut C4GroupHandle) -> bool;
 }
+extern "C" {
+    pub fn c4_group_handle_is_child_group(handle: *mut C4GroupHandle,
+                                          name: *const ::std::os::raw::c_char,
+                                          packed: *mut bool)
+     -> bool;
+}
+extern "C" {
+    pub fn c4_group_handle_add_from_memory(handle: *mut C4GroupHandle,
+                                           name: *const ::std::os::raw::c_char,
+                                           data: *const ::std::os::raw::c_void,
+                                           size: usize) -> bool;
+}
+extern "C" {
+    pub fn c4_group_handle_add_from_file(handle: *mut C4GroupHandle,
+                                         path: *const ::std::os::raw::c_char,
+                                         name: *const ::std::os::raw::c_char)
+     -> bool;
+}
+extern "C" {
+    pub fn c4_group_handle_delete(handle: *mut C4GroupHandle,
+                                  wildcard: *const ::std::os::raw::c_char,
+                                  recursive: bool) -> bool;
+}
+extern "C" {
+    pub fn c4_group_handle_save(handle: *mut C4GroupHandle) -> bool;
+}
+extern "C" {
+    pub fn c4_group_pack_directory(path: *const ::std::os::raw::c_char) -> bool;
+}
+extern "C" {
+    pub fn c4_group_pack_directory_to(path: *const ::std::os::raw::c_char,
+                                      to: *const ::std::os::raw::c_char)
+     -> bool;
+}
+extern "C" {
+    pub fn c4_group_unpack_directory(path: *const ::std::os::raw::c_char)
+     -> bool;
+}
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
 pub struct _C4MapgenHandle([u8; 0]);
 pub type C4MapgenHandle = _C4MapgenHandle;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _C4MapgenValue {
+    pub standard: i32,
+    pub random: i32,
+    pub min: i32,
+    pub max: i32,
+}
+pub type C4MapgenValue = _C4MapgenValue;
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
+pub struct _C4MapgenLandscape {
+    pub map_width: C4MapgenValue,
+    pub map_height: C4MapgenValue,
+    pub map_zoom: C4MapgenValue,
+    pub map_player_extend: i32,
+    pub exact_landscape: bool,
+    pub bottom_open: bool,
+    pub top_open: bool,
+    pub left_open: i32,
+    pub right_open: i32,
+    pub auto_scan_side_open: bool,
+    pub sky: *const ::std::os::raw::c_char,
+    pub sky_fade: [i32; 6usize],
+    pub amplitude: C4MapgenValue,
+    pub phase: C4MapgenValue,
+    pub period: C4MapgenValue,
+    pub random: C4MapgenValue,
+    pub liquid_level: C4MapgenValue,
+    pub gravity: C4MapgenValue,
+    pub material: *const ::std::os::raw::c_char,
+    pub liquid: *const ::std::os::raw::c_char,
+    pub layer_names: *const *const ::std::os::raw::c_char,
+    pub layer_counts: *const i32,
+    pub layer_count: usize,
+}
+pub type C4MapgenLandscape = _C4MapgenLandscape;
 extern "C" {
     pub fn c4_log_handle_clear();
 }
@@ -184,6 +272,12 @@ extern "C" {
                                                index: ::std::os::raw::c_uint)
      -> *mut C4MaterialHandle;
 }
+extern "C" {
+    pub fn c4_material_map_handle_get_index(material_map:
+                                                *mut C4MaterialMapHandle,
+                                            name: *const ::std::os::raw::c_char)
+     -> i32;
+}
 extern "C" {
     pub fn c4_material_handle_get_name(material: *mut C4MaterialHandle)
      -> *const ::std::os::raw::c_char;
@@ -195,6 +289,28 @@ extern "C" {
 }
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
+pub struct _C4MaterialProperties {
+    pub density: i32,
+    pub friction: i32,
+    pub dig_free: i32,
+    pub blast_free: i32,
+    pub instable: i32,
+    pub placement: i32,
+    pub incendiary: i32,
+    pub extinguisher: i32,
+    pub light_angle: i32,
+    pub light_emit: [i32; 3usize],
+    pub light_spot: [i32; 3usize],
+    pub min_shape_overlap: i32,
+}
+pub type C4MaterialProperties = _C4MaterialProperties;
+extern "C" {
+    pub fn c4_material_handle_get_properties(material: *mut C4MaterialHandle,
+                                             properties:
+                                                 *mut C4MaterialProperties);
+}
+#[repr(C)]
+#[derive(Debug, Copy, Clone)]
 pub struct _C4ScenparHandle([u8; 0]);
 pub type C4ScenparHandle = _C4ScenparHandle;
 extern "C" {
@@ -218,6 +334,63 @@ extern "C" {
                                        id: *const ::std::os::raw::c_char,
                                        value: i32, only_if_larger: bool);
 }
+extern "C" {
+    pub fn c4_scenpar_handle_get_num_defs(handle: *mut C4ScenparHandle)
+     -> usize;
+}
+extern "C" {
+    pub fn c4_scenpar_handle_get_def_id(handle: *mut C4ScenparHandle,
+                                        index: usize)
+     -> *const ::std::os::raw::c_char;
+}
+extern "C" {
+    pub fn c4_scenpar_handle_get_def_name(handle: *mut C4ScenparHandle,
+                                          index: usize)
+     -> *const ::std::os::raw::c_char;
+}
+extern "C" {
+    pub fn c4_scenpar_handle_get_def_description(handle: *mut C4ScenparHandle,
+                                                 index: usize)
+     -> *const ::std::os::raw::c_char;
+}
+extern "C" {
+    pub fn c4_scenpar_handle_get_def_type(handle: *mut C4ScenparHandle,
+                                          index: usize)
+     -> i32;
+}
+extern "C" {
+    pub fn c4_scenpar_handle_get_def_default(handle: *mut C4ScenparHandle,
+                                             index: usize)
+     -> i32;
+}
+extern "C" {
+    pub fn c4_scenpar_handle_get_def_is_achievement(handle: *mut C4ScenparHandle,
+                                                    index: usize)
+     -> bool;
+}
+extern "C" {
+    pub fn c4_scenpar_handle_get_def_num_options(handle: *mut C4ScenparHandle,
+                                                 index: usize)
+     -> usize;
+}
+extern "C" {
+    pub fn c4_scenpar_handle_get_def_option_value(handle: *mut C4ScenparHandle,
+                                                  index: usize,
+                                                  option: usize)
+     -> i32;
+}
+extern "C" {
+    pub fn c4_scenpar_handle_get_def_option_name(handle: *mut C4ScenparHandle,
+                                                 index: usize,
+                                                 option: usize)
+     -> *const ::std::os::raw::c_char;
+}
+extern "C" {
+    pub fn c4_scenpar_handle_get_def_option_description(handle: *mut C4ScenparHandle,
+                                                        index: usize,
+                                                        option: usize)
+     -> *const ::std::os::raw::c_char;
+}
 extern "C" {
     pub fn c4_mapgen_handle_init_script_engine();
 }
@@ -227,6 +400,11 @@ extern "C" {
 extern "C" {
     pub fn c4_mapgen_handle_set_map_library(group_handle: *mut C4GroupHandle);
 }
+extern "C" {
+    pub fn c4_mapgen_handle_load_definition(group_handle: *mut C4GroupHandle,
+                                            id: *const ::std::os::raw::c_char)
+     -> bool;
+}
 extern "C" {
     pub fn c4_mapgen_handle_load_system(group_handle: *mut C4GroupHandle);
 }
@@ -249,23 +427,35 @@ extern "C" {
                                        scenpar: *mut C4ScenparHandle,
                                        material_map: *mut C4MaterialMapHandle,
                                        texture_map: *mut C4TextureMapHandle,
-                                       map_width: ::std::os::raw::c_uint,
-                                       map_height: ::std::os::raw::c_uint)
+                                       landscape: *const C4MapgenLandscape,
+                                       player_count: i32)
      -> *mut C4MapgenHandle;
 }
 extern "C" {
     pub fn c4_mapgen_handle_new(filename: *const ::std::os::raw::c_char,
                                 source: *const ::std::os::raw::c_char,
                                 script_path: *const ::std::os::raw::c_char,
+                                script_source: *const ::std::os::raw::c_char,
                                 material_map: *mut C4MaterialMapHandle,
                                 texture_map: *mut C4TextureMapHandle,
-                                map_width: ::std::os::raw::c_uint,
-                                map_height: ::std::os::raw::c_uint)
+                                landscape: *const C4MapgenLandscape,
+                                player_count: i32)
      -> *mut C4MapgenHandle;
 }
 extern "C" {
     pub fn c4_mapgen_handle_free(mapgen: *mut C4MapgenHandle);
 }
+extern "C" {
+    pub fn c4_mapgen_handle_parse_landscape(filename: *const ::std::os::raw::c_char,
+                                            source: *const ::std::os::raw::c_char,
+                                            material_map: *mut C4MaterialMapHandle,
+                                            texture_map: *mut C4TextureMapHandle,
+                                            error: *mut *mut ::std::os::raw::c_char)
+     -> *mut ::std::os::raw::c_char;
+}
+extern "C" {
+    pub fn c4_mapgen_handle_free_string(string: *mut ::std::os::raw::c_char);
+}
 extern "C" {
     pub fn c4_mapgen_handle_get_map(mapgen: *mut C4MapgenHandle)
      -> *const ::std::os::raw::c_uchar;
@@ -288,6 +478,14 @@ extern "C" {
                                     texture_map: *mut C4TextureMapHandle)
      -> bool;
 }
+extern "C" {
+    pub fn c4_mapgen_handle_get_map_bmp(mapgen: *mut C4MapgenHandle,
+                                        bg: bool,
+                                        size: *mut usize,
+                                        material_map: *mut C4MaterialMapHandle,
+                                        texture_map: *mut C4TextureMapHandle)
+     -> *const ::std::os::raw::c_uchar;
+}
 extern "C" {
     pub fn c4_mapgen_handle_get_width(mapgen: *mut C4MapgenHandle)
      -> ::std::os::raw::c_uint;
@@ -315,6 +513,9 @@ extern "C" {
 extern "C" {
     pub fn c4_random_handle_seed(seed: ::std::os::raw::c_uint);
 }
+extern "C" {
+    pub fn c4_random_handle_next_seed() -> ::std::os::raw::c_uint;
+}
 extern "C" {
     pub fn c4_version_get() -> *const ::std::os::raw::c_char;
 }
@@ -0,0 +1,244 @@
+//! Regression tests comparing rendered maps against stored expected outputs.
+//!
+//! A test directory contains the map files and a `Golden.txt` manifest with one section per test
+//! case:
+//!
+//! ```text
+//! [Cave]
+//! File=Cave.c
+//! Seed=42
+//! Width=120
+//! Height=80
+//! Players=2
+//! Scenpar=Difficulty=2,MapSize=1
+//! ```
+//!
+//! Only `File` is required. Scenario parameters are taken from a ParameterDefs.txt in the test
+//! directory. The expected texture map indices are stored as grayscale PNGs in the `expected`
+//! subdirectory (`Cave.fg.png` and `Cave.bg.png`).
+
+use crate::easy::{Easy, load_scenpar};
+use crate::errors::*;
+use crate::ini::IniFile;
+use crate::scenario::parse_name_list;
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use error_chain::bail;
+use image::{GrayImage, ImageBuffer};
+
+/// Name of the manifest file in a test directory.
+pub const MANIFEST: &str = "Golden.txt";
+
+/// A map to render with fixed settings.
+#[derive(Clone, Debug)]
+pub struct GoldenCase {
+    pub name: String,
+    /// Map.c or Landscape.txt, relative to the test directory.
+    pub file: PathBuf,
+    pub seed: u32,
+    pub width: u32,
+    pub height: u32,
+    /// Startup player count, defaults to the one set on the map generator.
+    pub players: Option<i32>,
+    /// Scenario parameter values.
+    pub scenpar: Vec<(String, i32)>,
+}
+
+/// Outcome of a single test case.
+#[derive(Debug)]
+pub enum GoldenResult {
+    /// The map matches the expected output.
+    Passed,
+    /// The expected output was written (update mode).
+    Updated,
+    /// There is no expected output yet.
+    Missing,
+    /// The map differs from the expected output.
+    Mismatch(Mismatch),
+    /// The map couldn't be rendered or compared.
+    Failed(Error),
+}
+
+#[derive(Debug)]
+pub struct Mismatch {
+    pub expected_size: (u32, u32),
+    pub actual_size: (u32, u32),
+    /// Number of differing foreground pixels. Only compared if the sizes match.
+    pub fg_pixels: usize,
+    /// Number of differing background pixels. Only compared if the sizes match.
+    pub bg_pixels: usize,
+}
+
+impl GoldenResult {
+    pub fn is_ok(&self) -> bool {
+        matches!(self, GoldenResult::Passed | GoldenResult::Updated)
+    }
+}
+
+/// The test cases of a directory.
+pub struct GoldenSuite {
+    dir: PathBuf,
+    cases: Vec<GoldenCase>,
+}
+
+impl GoldenSuite {
+    /// Loads the manifest of a test directory.
+    pub fn load<P: AsRef<Path>>(dir: P) -> Result<GoldenSuite> {
+        let dir = dir.as_ref().to_owned();
+        let manifest = fs::read_to_string(dir.join(MANIFEST))
+            .chain_err(|| format!("couldn't read {}", dir.join(MANIFEST).display()))?;
+        let cases = parse_manifest(&manifest)
+            .chain_err(|| format!("invalid {}", dir.join(MANIFEST).display()))?;
+        Ok(GoldenSuite { dir, cases })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn cases(&self) -> &[GoldenCase] {
+        &self.cases
+    }
+
+    /// Renders all cases and compares them with the expected outputs.
+    ///
+    /// In update mode, the expected outputs are overwritten with the rendered maps instead.
+    pub fn run(&self, easy: &Easy, update: bool) -> Vec<(&GoldenCase, GoldenResult)> {
+        self.cases.iter()
+            .map(|case| {
+                let result = match self.run_case(easy, case, update) {
+                    Ok(result) => result,
+                    Err(e) => GoldenResult::Failed(e),
+                };
+                (case, result)
+            })
+            .collect()
+    }
+
+    fn run_case(&self, easy: &Easy, case: &GoldenCase, update: bool) -> Result<GoldenResult> {
+        let scenpar = if case.scenpar.is_empty() {
+            None
+        } else {
+            let mut scenpar = load_scenpar(&self.dir)?;
+            for (id, value) in &case.scenpar {
+                scenpar.select(id, *value)?;
+            }
+            Some(scenpar)
+        };
+        let mut cfg = easy.build();
+        cfg.filename(self.dir.join(&case.file).to_str().unwrap())
+           .width(case.width)
+           .height(case.height)
+           .seed(case.seed);
+        if let Some(players) = case.players {
+            cfg.players(players);
+        }
+        if let Some(ref scenpar) = scenpar {
+            cfg.scenpar(scenpar);
+        }
+        let map_handle = cfg.render()?;
+        let (width, height) = (map_handle.width(), map_handle.height());
+        let fg = ImageBuffer::from_raw(width, height, map_handle.map_indices()).unwrap();
+        let bg = ImageBuffer::from_raw(width, height, map_handle.map_bg_indices()).unwrap();
+
+        let (fg_path, bg_path) = self.expected_paths(case);
+        if update {
+            fs::create_dir_all(fg_path.parent().unwrap())?;
+            save(&fg, &fg_path)?;
+            save(&bg, &bg_path)?;
+            return Ok(GoldenResult::Updated);
+        }
+        if !fg_path.exists() || !bg_path.exists() {
+            return Ok(GoldenResult::Missing);
+        }
+        let expected_fg = load(&fg_path)?;
+        let expected_bg = load(&bg_path)?;
+        let mismatch = Mismatch {
+            expected_size: expected_fg.dimensions(),
+            actual_size: fg.dimensions(),
+            fg_pixels: count_differences(&expected_fg, &fg),
+            bg_pixels: count_differences(&expected_bg, &bg),
+        };
+        if mismatch.expected_size == mismatch.actual_size && mismatch.fg_pixels == 0 && mismatch.bg_pixels == 0 {
+            Ok(GoldenResult::Passed)
+        } else {
+            Ok(GoldenResult::Mismatch(mismatch))
+        }
+    }
+
+    /// Returns where the expected foreground and background indices of a case are stored.
+    pub fn expected_paths(&self, case: &GoldenCase) -> (PathBuf, PathBuf) {
+        let dir = self.dir.join("expected");
+        (dir.join(format!("{}.fg.png", case.name)), dir.join(format!("{}.bg.png", case.name)))
+    }
+}
+
+fn parse_manifest(manifest: &str) -> Result<Vec<GoldenCase>> {
+    let ini = IniFile::parse(manifest);
+    let mut cases = Vec::new();
+    for section in ini.sections.iter().filter(|s| !s.name.is_empty()) {
+        let mut case = GoldenCase {
+            name: section.name.clone(),
+            file: PathBuf::new(),
+            seed: 0,
+            width: 200,
+            height: 200,
+            players: None,
+            scenpar: Vec::new(),
+        };
+        for (key, value) in &section.entries {
+            let invalid = || format!("invalid {} '{}' in [{}]", key, value, section.name);
+            match key.as_str() {
+                "File"    => case.file = PathBuf::from(value),
+                "Seed"    => case.seed = value.parse().chain_err(invalid)?,
+                "Width"   => case.width = value.parse().chain_err(invalid)?,
+                "Height"  => case.height = value.parse().chain_err(invalid)?,
+                "Players" => case.players = Some(value.parse().chain_err(invalid)?),
+                "Scenpar" => case.scenpar = parse_name_list(key, value)?,
+                _ => bail!("unknown key {} in [{}]", key, section.name),
+            }
+        }
+        if section.get("File").is_none() {
+            bail!("no File in [{}]", section.name);
+        }
+        cases.push(case);
+    }
+    Ok(cases)
+}
+
+fn save(image: &GrayImage, path: &Path) -> Result<()> {
+    image.save(path)
+        .chain_err(|| format!("couldn't write {}", path.display()))
+}
+
+fn load(path: &Path) -> Result<GrayImage> {
+    let image = image::open(path)
+        .chain_err(|| format!("couldn't read {}", path.display()))?;
+    Ok(image.into_luma8())
+}
+
+fn count_differences(expected: &GrayImage, actual: &GrayImage) -> usize {
+    if expected.dimensions() != actual.dimensions() {
+        return 0;
+    }
+    expected.pixels().zip(actual.pixels()).filter(|(a, b)| a != b).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_manifest;
+
+    #[test]
+    fn manifest() {
+        let cases = parse_manifest("[Cave]\nFile=Cave.c\nSeed=42\nScenpar=Difficulty=2,MapSize=1\n\n[Flat]\nFile=Landscape.txt\nWidth=50\n").unwrap();
+        assert_eq!(cases.len(), 2);
+        assert_eq!(cases[0].name, "Cave");
+        assert_eq!(cases[0].seed, 42);
+        assert_eq!(cases[0].scenpar, vec![("Difficulty".to_owned(), 2), ("MapSize".to_owned(), 1)]);
+        assert_eq!(cases[1].width, 50);
+        assert_eq!(cases[1].height, 200);
+        assert!(parse_manifest("[Broken]\nSeed=1\n").is_err());
+    }
+}
@@ -2,9 +2,11 @@ use crate::ffi::*;
 use crate::errors::*;
 use crate::Handle;
 
-use std::os::raw::{c_void, c_char};
+use std::os::raw::c_void;
 use std::ffi::{CStr, CString};
+use std::path::Path;
 use std::ptr;
+use std::vec;
 
 use error_chain::bail;
 
@@ -12,10 +14,30 @@ pub struct Group {
     handle: *mut C4GroupHandle,
 }
 
+/// An entry of a group, as returned by `Group::entries`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GroupEntry {
+    /// Name for display. Names that aren't valid UTF-8 are converted lossily, use `raw_name` to
+    /// access such entries.
+    pub name: String,
+    /// Name as stored in the group.
+    pub raw_name: Vec<u8>,
+    /// Size in bytes.
+    pub size: usize,
+    /// Whether the entry is a child group (packed or folder) that can be opened with
+    /// `Group::open_as_child`.
+    pub is_group: bool,
+    /// Whether the child group is a packed file instead of a folder. False for other entries.
+    pub is_packed: bool,
+}
+
 macro_rules! group_error {
     ($handle:expr) => {
         bail!(ErrorKind::Group(CStr::from_ptr(c4_group_handle_get_error($handle)).to_string_lossy().into_owned()));
     };
+    ($handle:expr, $($context:tt)+) => {
+        bail!(ErrorKind::Group(format!("{}: {}", format!($($context)+), CStr::from_ptr(c4_group_handle_get_error($handle)).to_string_lossy())));
+    };
 }
 
 impl Group {
@@ -38,10 +60,11 @@ impl Group {
         }
     }
 
-    pub fn open_as_child(mother: &Group, name: &str, exclusive: bool, create: bool) -> Result<Group> {
+    /// Opens a child group. The name may be an entry's `raw_name`.
+    pub fn open_as_child<N: AsRef<[u8]>>(mother: &Group, name: N, exclusive: bool, create: bool) -> Result<Group> {
         let group = Group::new();
         unsafe {
-            if c4_group_handle_open_as_child(group.handle, mother.handle, CString::new(name).unwrap().as_ptr(), exclusive, create) {
+            if c4_group_handle_open_as_child(group.handle, mother.handle, CString::new(name.as_ref()).unwrap().as_ptr(), exclusive, create) {
                 Ok(group)
             } else {
                 group_error!(group.handle);
@@ -59,10 +82,84 @@ impl Group {
 
     pub fn full_name(&self) -> String {
         unsafe {
-            CStr::from_ptr(c4_group_handle_get_full_name(self.handle))
-                .to_string_lossy()
-                .into_owned()
+            let full_name = c4_group_handle_get_full_name(self.handle);
+            let result = CStr::from_ptr(full_name).to_string_lossy().into_owned();
+            c4_group_handle_free_string(full_name);
+            result
+        }
+    }
+
+    /// Whether the group is a packed file instead of a folder.
+    pub fn is_packed(&self) -> bool {
+        unsafe {
+            !c4_group_handle_is_folder(self.handle)
+        }
+    }
+
+    /// Returns all entries of the group, see `find`.
+    pub fn entries(&mut self) -> Result<Vec<GroupEntry>> {
+        self.find("*")
+    }
+
+    /// Returns the entries with names matching a wildcard, e.g. `*.ocd`. `*` matches any
+    /// sequence of characters and `?` a single character.
+    pub fn find(&mut self, wildcard: &str) -> Result<Vec<GroupEntry>> {
+        self.iter_entries(wildcard).collect()
+    }
+
+    /// Iterates over the entries with names matching a wildcard, see `find`.
+    ///
+    /// The matching entries are listed when the iterator is created, so the group can be used
+    /// while iterating, e.g. to load the entries.
+    pub fn iter_entries(&mut self, wildcard: &str) -> Entries {
+        let entries = if unsafe { c4_group_handle_is_open(self.handle) } {
+            let wildcard = CString::new(wildcard).unwrap();
+            self.rewind();
+            let mut found = Vec::new();
+            while let Some(entry) = self.find_next(&wildcard) {
+                found.push(entry);
+            }
+            self.rewind();
+            found.into_iter()
+                .map(|(name, size)| {
+                    let (is_group, is_packed) = self.child_group_kind(&name);
+                    Ok(GroupEntry {
+                        name: name.to_string_lossy().into_owned(),
+                        raw_name: name.into_bytes(),
+                        size,
+                        is_group,
+                        is_packed,
+                    })
+                })
+                .collect()
+        } else {
+            vec![Err(ErrorKind::Group(format!("{} isn't open", self.name())).into())]
+        };
+        Entries { entries: entries.into_iter() }
+    }
+
+    /// Returns all entries of the group and its child groups, depth-first.
+    ///
+    /// Each entry comes with its path relative to this group, with `/` as separator. Child
+    /// groups are listed before their contents.
+    pub fn walk(&mut self) -> Result<Vec<(String, GroupEntry)>> {
+        let mut result = Vec::new();
+        self.walk_into("", &mut result)?;
+        Ok(result)
+    }
+
+    fn walk_into(&mut self, prefix: &str, result: &mut Vec<(String, GroupEntry)>) -> Result<()> {
+        for entry in self.entries()? {
+            let path = format!("{}{}", prefix, entry.name);
+            if entry.is_group {
+                let mut child = Group::open_as_child(self, &entry.raw_name, false, false)?;
+                result.push((path.clone(), entry));
+                child.walk_into(&format!("{}/", path), result)?;
+            } else {
+                result.push((path, entry));
+            }
         }
+        Ok(())
     }
 
     pub fn rewind(&mut self) {
@@ -71,20 +168,129 @@ impl Group {
         }
     }
 
-    pub fn load_entry(&self, name: &str) -> Result<Vec<u8>> {
+    /// Finds the next entry matching the wildcard from the search position of the group. Returns
+    /// the raw name and size.
+    fn find_next(&mut self, wildcard: &CStr) -> Option<(CString, usize)> {
+        unsafe {
+            let mut size: usize = 0;
+            let name = c4_group_handle_find_next_entry_name(self.handle, wildcard.as_ptr(), &mut size);
+            if name.is_null() {
+                return None;
+            }
+            let result = CStr::from_ptr(name).to_owned();
+            c4_group_handle_free_string(name);
+            Some((result, size))
+        }
+    }
+
+    /// Returns whether the entry is a child group and whether that group is packed.
+    fn child_group_kind(&self, name: &CStr) -> (bool, bool) {
+        unsafe {
+            let mut packed = false;
+            let is_group = c4_group_handle_is_child_group(self.handle, name.as_ptr(), &mut packed);
+            (is_group, is_group && packed)
+        }
+    }
+
+    /// Reads an entry. The name may be an entry's `raw_name`.
+    pub fn load_entry<N: AsRef<[u8]>>(&self, name: N) -> Result<Vec<u8>> {
+        let name = name.as_ref();
         unsafe {
-            let entry = CString::new(name.to_string()).unwrap();
+            let entry = CString::new(name).unwrap();
             let mut size: usize = 0;
             if !c4_group_handle_access_entry(self.handle, entry.as_ptr(), &mut size, ptr::null_mut(), false) {
-                group_error!(self.handle);
+                group_error!(self.handle, "couldn't find {}", String::from_utf8_lossy(name));
             }
             let mut data: Vec<u8> = vec![0; size];
             if !c4_group_handle_read(self.handle, data.as_mut_ptr() as *mut c_void, size) {
-                group_error!(self.handle);
+                group_error!(self.handle, "couldn't read {}", String::from_utf8_lossy(name));
             }
             Ok(data)
         }
     }
+
+    /// Adds an entry with the given contents, replacing an existing entry of the same name.
+    ///
+    /// Packed groups are only written by `save` or when the group is dropped.
+    pub fn add_entry(&mut self, name: &str, data: &[u8]) -> Result<()> {
+        unsafe {
+            let name = CString::new(name).unwrap();
+            if !c4_group_handle_add_from_memory(self.handle, name.as_ptr(), data.as_ptr() as *const c_void, data.len()) {
+                group_error!(self.handle);
+            }
+        }
+        Ok(())
+    }
+
+    /// Copies a file or directory into the group as entry `name`. See `add_entry`.
+    pub fn add_file<P: AsRef<Path>>(&mut self, path: P, name: &str) -> Result<()> {
+        unsafe {
+            let path = CString::new(path.as_ref().to_str().unwrap()).unwrap();
+            let name = CString::new(name).unwrap();
+            if !c4_group_handle_add_from_file(self.handle, path.as_ptr(), name.as_ptr()) {
+                group_error!(self.handle);
+            }
+        }
+        Ok(())
+    }
+
+    /// Deletes all entries matching the wildcard, see `find`. With `recursive`, matching entries
+    /// of child groups are deleted as well.
+    pub fn delete_entries(&mut self, wildcard: &str, recursive: bool) -> Result<()> {
+        unsafe {
+            let wildcard = CString::new(wildcard).unwrap();
+            if !c4_group_handle_delete(self.handle, wildcard.as_ptr(), recursive) {
+                group_error!(self.handle);
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes changes to disk.
+    ///
+    /// Saving a child group writes it into its parent, which then has to be saved as well.
+    pub fn save(&mut self) -> Result<()> {
+        unsafe {
+            if !c4_group_handle_save(self.handle) {
+                group_error!(self.handle);
+            }
+        }
+        Ok(())
+    }
+
+    /// Packs a folder group (e.g. a `.ocs` directory) in place.
+    pub fn pack_directory<P: AsRef<Path>>(path: P) -> Result<()> {
+        let path = path.as_ref();
+        unsafe {
+            if !c4_group_pack_directory(CString::new(path.to_str().unwrap()).unwrap().as_ptr()) {
+                bail!(ErrorKind::Group(format!("couldn't pack {}", path.display())));
+            }
+        }
+        Ok(())
+    }
+
+    /// Packs a folder group into a new file, leaving the folder as it is.
+    pub fn pack_directory_to<P: AsRef<Path>, Q: AsRef<Path>>(path: P, to: Q) -> Result<()> {
+        let (path, to) = (path.as_ref(), to.as_ref());
+        unsafe {
+            if !c4_group_pack_directory_to(CString::new(path.to_str().unwrap()).unwrap().as_ptr(),
+                                           CString::new(to.to_str().unwrap()).unwrap().as_ptr()) {
+                bail!(ErrorKind::Group(format!("couldn't pack {} to {}", path.display(), to.display())));
+            }
+        }
+        Ok(())
+    }
+
+    /// Unpacks a packed group into a folder of the same name, including packed child groups.
+    pub fn unpack_directory<P: AsRef<Path>>(path: P) -> Result<()> {
+        let path = path.as_ref();
+        unsafe {
+            if !c4_group_unpack_directory(CString::new(path.to_str().unwrap()).unwrap().as_ptr()) {
+                bail!(ErrorKind::Group(format!("couldn't unpack {}", path.display())));
+            }
+        }
+        Ok(())
+    }
 }
 
 impl Drop for Group {
@@ -101,25 +307,15 @@ impl Handle<C4GroupHandle> for Group {
     }
 }
 
+/// Iterator over group entries, see `Group::iter_entries`.
+pub struct Entries {
+    entries: vec::IntoIter<Result<GroupEntry>>,
+}
 
-impl Iterator for Group {
-    type Item = String;
+impl Iterator for Entries {
+    type Item = Result<GroupEntry>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        unsafe {
-            let mut buf: Vec<u8> = vec![0; 512];
-            let wildcard = CString::new("*").unwrap();
-            if c4_group_handle_find_next_entry(self.handle, wildcard.as_ptr(), ptr::null_mut(), buf.as_mut_slice().as_mut_ptr() as *mut c_char, false) {
-                if let Some(pos) = buf.iter().position(|&b| b == 0) {
-                    buf.truncate(pos + 1);
-                    let name = CStr::from_bytes_with_nul(&buf).unwrap().to_string_lossy().into_owned();
-                    Some(name)
-                } else {
-                    panic!("string not null-terminated");
-                }
-            } else {
-                None
-            }
-        }
+        self.entries.next()
     }
 }
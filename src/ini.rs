@@ -0,0 +1,148 @@
+/// Parser for the ini-like text files used by OpenClonk (Scenario.txt, DefCore.txt, ...).
+///
+/// Sections and entries are kept in file order. Entries before the first section header end up
+/// in a section with an empty name.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct IniFile {
+    pub sections: Vec<IniSection>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub(crate) struct IniSection {
+    pub name: String,
+    pub entries: Vec<(String, String)>,
+}
+
+impl IniFile {
+    pub fn parse(source: &str) -> IniFile {
+        let mut file = IniFile::default();
+        for line in source.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(';') || line.starts_with('#') || line.starts_with("//") {
+                continue;
+            }
+            if line.starts_with('[') && line.ends_with(']') {
+                file.sections.push(IniSection {
+                    name: line[1..line.len() - 1].trim().to_owned(),
+                    entries: Vec::new(),
+                });
+            } else if let Some(pos) = line.find('=') {
+                if file.sections.is_empty() {
+                    file.sections.push(IniSection::default());
+                }
+                let section = file.sections.last_mut().unwrap();
+                section.entries.push((line[..pos].trim().to_owned(), line[pos + 1..].trim().to_owned()));
+            }
+        }
+        file
+    }
+
+    /// Returns the first section with the given name.
+    pub fn section(&self, name: &str) -> Option<&IniSection> {
+        self.sections.iter().find(|s| s.name == name)
+    }
+}
+
+impl IniSection {
+    /// Returns the value of the last entry with the given key.
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries.iter().rev()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Sets entries of a section, keeping the rest of the text including comments and line endings.
+///
+/// Existing entries are replaced in place and missing ones appended to the section. The section
+/// is added at the end if there is none with the given name. Works on bytes, so files in other
+/// encodings than UTF-8 (usually Windows-1252) are kept as they are.
+pub(crate) fn set_entries(source: &[u8], section: &str, entries: &[(&str, String)]) -> Vec<u8> {
+    let newline: &[u8] = if source.windows(2).any(|w| w == b"\r\n") { b"\r\n" } else { b"\n" };
+    let source = source.strip_suffix(b"\n").unwrap_or(source);
+    let mut lines: Vec<Vec<u8>> = if source.is_empty() {
+        Vec::new()
+    } else {
+        source.split(|&b| b == b'\n')
+            .map(|l| l.strip_suffix(b"\r").unwrap_or(l).to_vec())
+            .collect()
+    };
+    let mut done = vec![false; entries.len()];
+    let mut in_section = false;
+    // Index after the last non-empty line of the (first) matching section.
+    let mut section_end = None;
+    for (i, raw_line) in lines.iter_mut().enumerate() {
+        let line = raw_line.trim_ascii().to_vec();
+        if line.starts_with(b"[") && line.ends_with(b"]") {
+            in_section = section_end.is_none() && line[1..line.len() - 1].trim_ascii() == section.as_bytes();
+            if in_section {
+                section_end = Some(i + 1);
+            }
+            continue;
+        }
+        if !in_section || line.is_empty() {
+            continue;
+        }
+        section_end = Some(i + 1);
+        if line.starts_with(b";") || line.starts_with(b"#") || line.starts_with(b"//") {
+            continue;
+        }
+        if let Some(pos) = line.iter().position(|&b| b == b'=') {
+            let key = line[..pos].trim_ascii();
+            if let Some(j) = entries.iter().position(|(k, _)| k.as_bytes() == key) {
+                *raw_line = format!("{}={}", entries[j].0, entries[j].1).into_bytes();
+                done[j] = true;
+            }
+        }
+    }
+    let missing = entries.iter().zip(&done)
+        .filter(|(_, &done)| !done)
+        .map(|((key, value), _)| format!("{}={}", key, value).into_bytes());
+    match section_end {
+        Some(end) => {
+            let missing: Vec<Vec<u8>> = missing.collect();
+            lines.splice(end..end, missing);
+        },
+        None => {
+            if lines.last().is_some_and(|l| !l.trim_ascii().is_empty()) {
+                lines.push(Vec::new());
+            }
+            lines.push(format!("[{}]", section).into_bytes());
+            lines.extend(missing);
+        },
+    }
+    let mut result = lines.join(newline);
+    result.extend_from_slice(newline);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IniFile, set_entries};
+
+    #[test]
+    fn parse() {
+        let ini = IniFile::parse("Foo=1\n[Head]\n; comment\nTitle = Test \n\n[Landscape]\nMapWidth=100,0,64,250\nMapWidth=120\n");
+        assert_eq!(ini.sections.len(), 3);
+        assert_eq!(ini.sections[0].get("Foo"), Some("1"));
+        assert_eq!(ini.section("Head").and_then(|s| s.get("Title")), Some("Test"));
+        let landscape = ini.section("Landscape").unwrap();
+        assert_eq!(landscape.entries.len(), 2);
+        assert_eq!(landscape.get("MapWidth"), Some("120"));
+        assert_eq!(landscape.get("MapHeight"), None);
+    }
+
+    #[test]
+    fn set() {
+        let source = "[Head]\r\nTitle=Test\r\n\r\n[Landscape]\r\n; comment=1\r\nMapWidth=100,0,64,250\r\n\r\n[Game]\r\nRules=Foo\r\n";
+        let entries = [("MapWidth", "80,0,80,80".to_owned()), ("ExactLandscape", "0".to_owned())];
+        assert_eq!(set_entries(source.as_bytes(), "Landscape", &entries),
+                   b"[Head]\r\nTitle=Test\r\n\r\n[Landscape]\r\n; comment=1\r\nMapWidth=80,0,80,80\r\nExactLandscape=0\r\n\r\n[Game]\r\nRules=Foo\r\n");
+        assert_eq!(set_entries(b"[Head]\nTitle=Test\n", "Landscape", &entries),
+                   b"[Head]\nTitle=Test\n\n[Landscape]\nMapWidth=80,0,80,80\nExactLandscape=0\n");
+        // Windows-1252 text is kept as it is.
+        assert_eq!(set_entries(b"[Head]\nTitle=H\xf6hle\n", "Landscape", &entries[1..]),
+                   b"[Head]\nTitle=H\xf6hle\n\n[Landscape]\nExactLandscape=0\n");
+        assert_eq!(set_entries(b"", "Landscape", &entries[1..]), b"[Landscape]\nExactLandscape=0\n");
+    }
+}
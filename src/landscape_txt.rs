@@ -0,0 +1,674 @@
+//! Parse tree of Landscape.txt maps.
+//!
+//! Landscape.txt describes maps with nested blocks of attributes, as in
+//!
+//! ```text
+//! overlay Caves { algo=rndchecker; a=4; zoomX=-50; mat=Tunnel; tex=tunnel; };
+//!
+//! map Main {
+//!   overlay { mat=Earth; tex=earth; y=30; turbulence=100;
+//!     Caves { };
+//!     point { x=50; y=50; };
+//!   } | overlay { mat=Water; tex=water; y=70%; hgt=10px; };
+//! };
+//! ```
+//!
+//! This is a separate parser following the engine's grammar, not the engine's own tree: the
+//! engine's `C4MCParser` builds its `C4MCNode`s while rendering and throws them away afterwards.
+//! The tree here is meant for tools like formatters and linters, so it keeps comments and names
+//! and values as written. It rejects the same syntax errors and unknown templates as the engine,
+//! but whether attribute names and values make sense is checked by the engine only. To compare
+//! both, `LandscapeTxt::outline` and `MapGen::parse_landscape` return the node trees in the same
+//! form.
+
+use crate::errors::*;
+
+use std::fmt;
+
+use error_chain::bail;
+
+/// A parsed Landscape.txt file.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LandscapeTxt {
+    /// Top-level statements, usually overlay templates and maps.
+    pub statements: Vec<Statement>,
+}
+
+/// An attribute or a node, each terminated by `;`, or a comment between them.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Statement {
+    Attribute(Attribute),
+    Node(Node),
+    Comment(Comment),
+}
+
+/// `name=value;`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Attribute {
+    pub name: String,
+    pub value: Value,
+    /// Line number in the source, starting at 1.
+    pub line: usize,
+}
+
+/// A `// line` or `/* block */` comment.
+///
+/// Comments within a statement, as in `overlay /* x */ { };`, are moved to the next place a
+/// statement may start.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Comment {
+    /// The comment including `//` or `/*` and `*/`.
+    pub text: String,
+    /// Whether the comment follows the end of the previous statement on the same line.
+    pub trailing: bool,
+    /// Line number in the source, starting at 1.
+    pub line: usize,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Number(Number),
+    /// `from-to`
+    Range(Number, Number),
+    /// Materials, textures, algorithms and the like.
+    Ident(String),
+}
+
+/// An integer with an optional unit.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Number {
+    pub value: i32,
+    pub unit: Unit,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Unit {
+    None,
+    /// `%`
+    Percent,
+    /// `px`
+    Pixels,
+}
+
+/// A block like `overlay Name { ... }`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node {
+    pub kind: NodeKind,
+    pub name: Option<String>,
+    pub body: Vec<Statement>,
+    /// Operator combining this node with the following node of the same statement, as in
+    /// `overlay { ... } | overlay { ... };`.
+    pub op: Option<Operator>,
+    /// Line number in the source, starting at 1.
+    pub line: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NodeKind {
+    Map,
+    Overlay,
+    Point,
+    /// A copy of a named node defined before.
+    Template(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Operator {
+    /// `|`
+    Or,
+    /// `&`
+    And,
+    /// `^`
+    Xor,
+}
+
+impl LandscapeTxt {
+    pub fn parse(source: &str) -> Result<LandscapeTxt> {
+        let mut parser = Parser { tokens: tokenize(source)?, pos: 0, comments: Vec::new() };
+        let statements = parser.parse_body(false)?;
+        // Like the engine, reject templates which aren't defined before their use.
+        expand(&statements, &mut Vec::new())?;
+        Ok(LandscapeTxt { statements })
+    }
+
+    /// Returns an outline of the node tree as the engine builds it, with one line per node: its
+    /// type, name and operator, indented by two spaces per level.
+    ///
+    /// Uses of templates start with unnamed copies of the template's child nodes, as in the engine.
+    pub fn outline(&self) -> Result<String> {
+        let mut outline = String::new();
+        write_outline(&mut outline, &expand(&self.statements, &mut Vec::new())?, 0);
+        Ok(outline)
+    }
+
+    /// Returns the top-level nodes.
+    pub fn nodes(&self) -> impl Iterator<Item = &Node> {
+        nodes(&self.statements)
+    }
+
+    /// Returns the map with the given name, or the first map for `None`.
+    pub fn map(&self, name: Option<&str>) -> Option<&Node> {
+        self.nodes().find(|n| n.kind == NodeKind::Map && (name.is_none() || n.name.as_deref() == name))
+    }
+}
+
+impl Node {
+    /// Returns the value of the last assignment to an attribute.
+    pub fn attribute(&self, name: &str) -> Option<&Value> {
+        self.body.iter().rev()
+            .find_map(|s| match s {
+                Statement::Attribute(a) if a.name == name => Some(&a.value),
+                _ => None,
+            })
+    }
+
+    /// Returns the child nodes.
+    pub fn children(&self) -> impl Iterator<Item = &Node> {
+        nodes(&self.body)
+    }
+
+    /// Calls `f` for this node and all nodes below it, parents first.
+    pub fn walk<F: FnMut(&Node)>(&self, f: &mut F) {
+        f(self);
+        for child in self.children() {
+            child.walk(f);
+        }
+    }
+}
+
+fn nodes(statements: &[Statement]) -> impl Iterator<Item = &Node> {
+    statements.iter().filter_map(|s| match s {
+        Statement::Node(n) => Some(n),
+        Statement::Attribute(_) | Statement::Comment(_) => None,
+    })
+}
+
+/// A node with templates replaced by the type and children of the template, see `outline`.
+#[derive(Clone)]
+struct Expanded {
+    kind: NodeKind,
+    name: Option<String>,
+    op: Option<Operator>,
+    children: Vec<Expanded>,
+}
+
+impl Expanded {
+    /// Copies the node for a template use, which drops the names.
+    fn copy(&self) -> Expanded {
+        Expanded { name: None, children: self.children.iter().map(Expanded::copy).collect(), ..self.clone() }
+    }
+}
+
+/// Expands templates like the engine's `C4MCNode::GetNodeByName`: the last node of that name
+/// before the use, in the same block or in the blocks around it.
+///
+/// `scopes` holds the named nodes of each enclosing block, with `None` for those still being
+/// expanded.
+fn expand(statements: &[Statement], scopes: &mut Vec<Vec<(Option<String>, Option<Expanded>)>>) -> Result<Vec<Expanded>> {
+    scopes.push(Vec::new());
+    let mut result = Vec::new();
+    for node in nodes(statements) {
+        let (kind, mut children) = match node.kind {
+            NodeKind::Template(ref template) => {
+                let found = scopes.iter().rev()
+                    .flat_map(|scope| scope.iter().rev())
+                    .find(|(name, _)| name.as_ref() == Some(template));
+                match found {
+                    Some((_, Some(t))) => (t.kind.clone(), t.children.iter().map(Expanded::copy).collect()),
+                    Some((_, None)) => bail!("line {}: template {} is used within itself", node.line, template),
+                    None => bail!("line {}: unknown template {}", node.line, template),
+                }
+            },
+            ref kind => (kind.clone(), Vec::new()),
+        };
+        scopes.last_mut().unwrap().push((node.name.clone(), None));
+        children.extend(expand(&node.body, scopes)?);
+        let expanded = Expanded { kind, name: node.name.clone(), op: node.op, children };
+        scopes.last_mut().unwrap().last_mut().unwrap().1 = Some(expanded.clone());
+        result.push(expanded);
+    }
+    scopes.pop();
+    Ok(result)
+}
+
+fn write_outline(outline: &mut String, nodes: &[Expanded], depth: usize) {
+    for node in nodes {
+        outline.push_str(&"  ".repeat(depth));
+        outline.push_str(&node.kind.to_string());
+        if let Some(ref name) = node.name {
+            outline.push(' ');
+            outline.push_str(name);
+        }
+        // The engine only keeps operators of overlays.
+        match node.op {
+            Some(op) if node.kind != NodeKind::Point => outline.push_str(&format!(" {}", op)),
+            _ => {},
+        }
+        outline.push('\n');
+        write_outline(outline, &node.children, depth + 1);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Ident(String),
+    Number(Number),
+    Eq,
+    BlockOpen,
+    BlockClose,
+    Semicolon,
+    Op(Operator),
+    Range,
+    /// Text and whether it's on the line of the previous token.
+    Comment(String, bool),
+}
+
+/// Splits the source into tokens with their line numbers.
+fn tokenize(source: &str) -> Result<Vec<(Token, usize)>> {
+    let mut tokens = Vec::new();
+    let mut chars = source.chars().peekable();
+    let mut line = 1;
+    while let Some(c) = chars.next() {
+        let token = match c {
+            '\n' => { line += 1; continue },
+            c if c.is_whitespace() => continue,
+            '/' if chars.peek() == Some(&'/') => {
+                let mut text = c.to_string();
+                while let Some(c) = chars.peek().filter(|&&c| c != '\n') {
+                    text.push(*c);
+                    chars.next();
+                }
+                let trailing = tokens.last().is_some_and(|&(_, l)| l == line);
+                Token::Comment(text.trim_end().to_owned(), trailing)
+            },
+            '/' if chars.peek() == Some(&'*') => {
+                let start = line;
+                let mut text = c.to_string();
+                let mut prev = ' ';
+                loop {
+                    match chars.next() {
+                        Some(c) => {
+                            text.push(c);
+                            if c == '/' && prev == '*' && text.len() > 3 {
+                                break;
+                            }
+                            if c == '\n' {
+                                line += 1;
+                            }
+                            prev = c;
+                        },
+                        None => bail!("line {}: unterminated comment", line),
+                    }
+                }
+                let trailing = tokens.last().is_some_and(|&(_, l)| l == start);
+                tokens.push((Token::Comment(text, trailing), start));
+                continue;
+            },
+            '=' => Token::Eq,
+            '{' => Token::BlockOpen,
+            '}' => Token::BlockClose,
+            ';' => Token::Semicolon,
+            '|' => Token::Op(Operator::Or),
+            '&' => Token::Op(Operator::And),
+            '^' => Token::Op(Operator::Xor),
+            // A minus after a number separates a range, otherwise it can only be the sign of a
+            // value. The engine rejects it anywhere else, as in `x=5--3`.
+            '-' if matches!(tokens.last(), Some((Token::Number(_), _))) => Token::Range,
+            '-' if !matches!(tokens.last(), Some((Token::Eq, _))) || !chars.peek().is_some_and(char::is_ascii_digit) => {
+                bail!("line {}: unexpected '-'", line)
+            },
+            '-' | '0'..='9' => {
+                let mut digits = c.to_string();
+                while let Some(&d) = chars.peek().filter(|d| d.is_ascii_digit()) {
+                    digits.push(d);
+                    chars.next();
+                }
+                let value = digits.parse().chain_err(|| format!("line {}: invalid number {}", line, digits))?;
+                let unit = match chars.peek() {
+                    Some('%') => { chars.next(); Unit::Percent },
+                    Some('p') => {
+                        chars.next();
+                        if chars.next() != Some('x') {
+                            bail!("line {}: invalid unit after {}", line, digits);
+                        }
+                        Unit::Pixels
+                    },
+                    _ => Unit::None,
+                };
+                Token::Number(Number { value, unit })
+            },
+            c if c.is_alphabetic() || c == '_' => {
+                let mut ident = c.to_string();
+                while let Some(&c) = chars.peek().filter(|c| c.is_alphanumeric() || **c == '_') {
+                    ident.push(c);
+                    chars.next();
+                }
+                Token::Ident(ident)
+            },
+            c => bail!("line {}: unexpected character '{}'", line, c),
+        };
+        tokens.push((token, line));
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    pos: usize,
+    /// Comments skipped within a statement, see `Comment`.
+    comments: Vec<Comment>,
+}
+
+impl Parser {
+    /// Position of the current token, skipping comments.
+    fn token_pos(&self) -> usize {
+        let mut pos = self.pos;
+        while let Some((Token::Comment(..), _)) = self.tokens.get(pos) {
+            pos += 1;
+        }
+        pos
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.token_pos()).map(|(t, _)| t)
+    }
+
+    /// Line of the current token, or of the last one at the end.
+    fn line(&self) -> usize {
+        self.tokens.get(self.token_pos()).or_else(|| self.tokens.last()).map_or(1, |&(_, line)| line)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let pos = self.token_pos();
+        for (token, line) in &self.tokens[self.pos..pos] {
+            if let Token::Comment(text, _) = token {
+                self.comments.push(Comment { text: text.clone(), trailing: false, line: *line });
+            }
+        }
+        let token = self.tokens.get(pos).map(|(t, _)| t.clone());
+        self.pos = pos + 1;
+        token
+    }
+
+    /// Adds the comments skipped before and those at the current position as statements.
+    fn take_comments(&mut self, statements: &mut Vec<Statement>) {
+        statements.extend(self.comments.drain(..).map(Statement::Comment));
+        while let Some((Token::Comment(text, trailing), line)) = self.tokens.get(self.pos) {
+            statements.push(Statement::Comment(Comment { text: text.clone(), trailing: *trailing, line: *line }));
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, token: Token, what: &str) -> Result<()> {
+        let line = self.line();
+        match self.next() {
+            Some(ref t) if *t == token => Ok(()),
+            _ => bail!("line {}: {} expected", line, what),
+        }
+    }
+
+    /// Parses statements until the end of the block or of the file.
+    fn parse_body(&mut self, in_block: bool) -> Result<Vec<Statement>> {
+        let mut statements = Vec::new();
+        loop {
+            self.take_comments(&mut statements);
+            let line = self.line();
+            let ident = match self.next() {
+                Some(Token::BlockClose) if in_block => return Ok(statements),
+                None if !in_block => return Ok(statements),
+                None => bail!("line {}: '}}' expected", line),
+                Some(Token::Ident(ident)) => ident,
+                Some(_) => bail!("line {}: attribute or block expected", line),
+            };
+            if self.peek() == Some(&Token::Eq) {
+                self.next();
+                let value = self.parse_value()?;
+                statements.push(Statement::Attribute(Attribute { name: ident, value, line }));
+                self.expect(Token::Semicolon, "';'")?;
+                continue;
+            }
+            // One or more nodes combined with operators.
+            let mut ident = ident;
+            let mut line = line;
+            loop {
+                let mut node = self.parse_node(ident, line)?;
+                line = self.line();
+                match self.next() {
+                    Some(Token::Op(op)) => {
+                        node.op = Some(op);
+                        statements.push(Statement::Node(node));
+                        ident = match self.next() {
+                            Some(Token::Ident(ident)) => ident,
+                            _ => bail!("line {}: block expected after operator", line),
+                        };
+                        line = self.line();
+                    },
+                    Some(Token::Semicolon) => {
+                        statements.push(Statement::Node(node));
+                        break;
+                    },
+                    _ => bail!("line {}: ';' expected", line),
+                }
+            }
+        }
+    }
+
+    /// Parses a node after its type identifier.
+    fn parse_node(&mut self, kind: String, line: usize) -> Result<Node> {
+        let kind = match kind.as_str() {
+            "map" => NodeKind::Map,
+            "overlay" => NodeKind::Overlay,
+            "point" => NodeKind::Point,
+            _ => NodeKind::Template(kind),
+        };
+        let name = match self.peek() {
+            Some(Token::Ident(name)) => {
+                let name = name.clone();
+                self.next();
+                Some(name)
+            },
+            _ => None,
+        };
+        self.expect(Token::BlockOpen, "'{'")?;
+        let body = self.parse_body(true)?;
+        Ok(Node { kind, name, body, op: None, line })
+    }
+
+    fn parse_value(&mut self) -> Result<Value> {
+        let line = self.line();
+        match self.next() {
+            Some(Token::Ident(ident)) => Ok(Value::Ident(ident)),
+            Some(Token::Number(from)) => {
+                if self.peek() != Some(&Token::Range) {
+                    return Ok(Value::Number(from));
+                }
+                self.next();
+                match self.next() {
+                    Some(Token::Number(to)) => Ok(Value::Range(from, to)),
+                    _ => bail!("line {}: number expected after '-'", line),
+                }
+            },
+            _ => bail!("line {}: value expected", line),
+        }
+    }
+}
+
+/// Formats the tree as Landscape.txt with two spaces indentation.
+impl fmt::Display for LandscapeTxt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write_body(f, &self.statements, 0)
+    }
+}
+
+fn write_body(f: &mut fmt::Formatter, statements: &[Statement], depth: usize) -> fmt::Result {
+    let indent = "  ".repeat(depth);
+    let mut continued = false;
+    // Whether the last line may get a trailing comment, i.e. it ends with a statement.
+    let mut line_open = false;
+    for statement in statements {
+        if line_open {
+            match statement {
+                Statement::Comment(c) if c.trailing => {
+                    writeln!(f, " {}", c.text)?;
+                    line_open = false;
+                    continue;
+                },
+                _ => writeln!(f)?,
+            }
+        }
+        match statement {
+            Statement::Comment(c) => writeln!(f, "{}{}", indent, c.text)?,
+            Statement::Attribute(a) => {
+                write!(f, "{}{}={};", indent, a.name, a.value)?;
+                line_open = true;
+            },
+            Statement::Node(node) => {
+                if !continued {
+                    write!(f, "{}", indent)?;
+                }
+                write!(f, "{}", node.kind)?;
+                if let Some(ref name) = node.name {
+                    write!(f, " {}", name)?;
+                }
+                writeln!(f, " {{")?;
+                write_body(f, &node.body, depth + 1)?;
+                write!(f, "{}}}", indent)?;
+                match node.op {
+                    Some(op) => write!(f, " {} ", op)?,
+                    None => {
+                        write!(f, ";")?;
+                        line_open = true;
+                    },
+                }
+                continued = node.op.is_some();
+            },
+        }
+    }
+    if line_open {
+        writeln!(f)?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for NodeKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NodeKind::Map => write!(f, "map"),
+            NodeKind::Overlay => write!(f, "overlay"),
+            NodeKind::Point => write!(f, "point"),
+            NodeKind::Template(name) => write!(f, "{}", name),
+        }
+    }
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Operator::Or => write!(f, "|"),
+            Operator::And => write!(f, "&"),
+            Operator::Xor => write!(f, "^"),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Value::Number(n) => write!(f, "{}", n),
+            Value::Range(from, to) => write!(f, "{}-{}", from, to),
+            Value::Ident(ident) => write!(f, "{}", ident),
+        }
+    }
+}
+
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.unit {
+            Unit::None => write!(f, "{}", self.value),
+            Unit::Percent => write!(f, "{}%", self.value),
+            Unit::Pixels => write!(f, "{}px", self.value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOURCE: &str = "// Caves
+overlay Caves { algo=rndchecker; a=4; zoomX=-50; mat=Tunnel; tex=tunnel; };
+
+map Main {
+  overlay { mat=Earth; tex=earth; y=30; turbulence=100;
+    Caves { };
+    point { x=50; y=50; };
+  } | overlay { mat=Water; /* blue */ tex=water; y=70%; hgt=10px; x=10-20; };
+};
+";
+
+    #[test]
+    fn parse() {
+        let txt = LandscapeTxt::parse(SOURCE).unwrap();
+        let caves = txt.nodes().next().unwrap();
+        assert_eq!((&caves.kind, caves.name.as_deref()), (&NodeKind::Overlay, Some("Caves")));
+        assert_eq!(caves.attribute("zoomX"), Some(&Value::Number(Number { value: -50, unit: Unit::None })));
+
+        let map = txt.map(None).unwrap();
+        assert_eq!((map.name.as_deref(), map.line), (Some("Main"), 4));
+        let overlays: Vec<&Node> = map.children().collect();
+        assert_eq!(overlays.len(), 2);
+        assert_eq!(overlays[0].op, Some(Operator::Or));
+        assert_eq!(overlays[0].attribute("mat"), Some(&Value::Ident("Earth".to_owned())));
+        assert_eq!(overlays[1].attribute("y"), Some(&Value::Number(Number { value: 70, unit: Unit::Percent })));
+        assert_eq!(overlays[1].attribute("x"), Some(&Value::Range(
+            Number { value: 10, unit: Unit::None }, Number { value: 20, unit: Unit::None })));
+        let mut kinds = Vec::new();
+        map.walk(&mut |n| kinds.push(n.kind.clone()));
+        assert_eq!(kinds, vec![NodeKind::Map, NodeKind::Overlay, NodeKind::Template("Caves".to_owned()), NodeKind::Point, NodeKind::Overlay]);
+
+        // Formatting keeps everything but line numbers and whitespace.
+        let formatted = txt.to_string();
+        assert!(formatted.starts_with("// Caves\noverlay Caves {\n"));
+        assert!(formatted.contains("  } | overlay {\n"));
+        assert!(formatted.contains("    mat=Water; /* blue */\n    tex=water;\n"));
+        assert_eq!(LandscapeTxt::parse(&formatted).unwrap().to_string(), formatted);
+        assert_eq!(LandscapeTxt::parse("map /* x */ { };").unwrap().to_string(), "map {\n  /* x */\n};\n");
+
+        assert!(LandscapeTxt::parse("map { mat=Earth }").is_err());
+        assert!(LandscapeTxt::parse("map {\n overlay { };").unwrap_err().to_string().starts_with("line 2"));
+    }
+
+    #[test]
+    fn reject() {
+        let error = |source| LandscapeTxt::parse(source).unwrap_err().to_string();
+        // Ranges take exactly two numbers, a minus can't appear anywhere else.
+        assert_eq!(error("map { x=5--3; };"), "line 1: unexpected '-'");
+        assert_eq!(error("map { x=5-; };"), "line 1: number expected after '-'");
+        assert_eq!(error("map { x=1-2-3; };"), "line 1: ';' expected");
+        assert_eq!(error("map { x=-; };"), "line 1: unexpected '-'");
+        assert_eq!(error("map { x=- 5; };"), "line 1: unexpected '-'");
+        assert_eq!(error("map { mat=Earth-Rock; };"), "line 1: unexpected '-'");
+        assert_eq!(error("map { overlay { } - overlay { }; };"), "line 1: unexpected '-'");
+        assert!(LandscapeTxt::parse("map { x=-5-3; y=5 - 3; };").is_ok());
+
+        // Templates have to be defined before their use and outside of themselves.
+        assert_eq!(error("map { Caves { }; };\noverlay Caves { };"), "line 1: unknown template Caves");
+        assert_eq!(error("overlay Caves {\n  Caves { };\n};"), "line 2: template Caves is used within itself");
+        assert_eq!(error("map { overlay Caves { }; };\nmap { Caves { }; };"), "line 2: unknown template Caves");
+    }
+
+    #[test]
+    fn outline() {
+        let txt = LandscapeTxt::parse(SOURCE).unwrap();
+        assert_eq!(txt.outline().unwrap(), "\
+overlay Caves
+map Main
+  overlay |
+    overlay
+    point
+  overlay
+");
+        let txt = LandscapeTxt::parse("overlay A { overlay B { point P { }; }; };\nmap { A X { overlay { }; }; };").unwrap();
+        assert_eq!(txt.outline().unwrap(), "overlay A\n  overlay B\n    point P\nmap\n  overlay X\n    overlay\n      point\n    overlay\n");
+    }
+}
@@ -4,6 +4,12 @@ mod mattex;
 mod scenpar;
 mod mapgen;
 mod misc;
+mod assets;
+mod ini;
+mod scenario;
+mod sheet;
+mod matset;
+mod preview;
 
 /// Used to access handle pointers across modules internally.
 trait Handle<T> {
@@ -11,11 +17,18 @@ trait Handle<T> {
 }
 
 pub mod easy;
-pub use group::Group;
-pub use mattex::{MaterialMap, TextureMap};
-pub use scenpar::Scenpar;
-pub use mapgen::{MapGen, MapGenHandle};
+pub mod golden;
+pub mod landscape_txt;
+pub use group::{Entries, Group, GroupEntry};
+pub use mattex::{Material, MaterialMap, MaterialProperties, TextureMap, TextureMapEntry};
+pub use scenpar::{Scenpar, ScenparDef, ScenparOption, ScenparType};
+pub use mapgen::{AlgoScript, AnimationFrame, MapGen, MapGenHandle, RenderSettings};
 pub use misc::*;
+pub use assets::{AssetBundle, AssetConfig, AssetReport};
+pub use scenario::{Scenario, Landscape, ScenarioValue};
+pub use sheet::{ContactSheet, TileRect};
+pub use matset::MaterialSet;
+pub use preview::TextureShape;
 
 mod errors {
     use error_chain::error_chain;
@@ -1,11 +1,16 @@
 use crate::ffi::*;
 use crate::errors::*;
 use crate::group::Group;
-use crate::mattex::{MaterialMap, TextureMap};
+use crate::mattex::{Material, MaterialMap, TextureMap};
 use crate::scenpar::Scenpar;
+use crate::scenario::{Landscape, ScenarioValue};
 use crate::Handle;
+use crate::misc::{seed_rng, next_seed};
+use crate::ini::set_entries;
+use crate::preview::{self, PreviewTexture};
 
 use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
 use std::slice;
 use std::cell::RefCell;
 use std::ptr;
@@ -14,12 +19,24 @@ use image::{self, ImageBuffer, RgbImage};
 use error_chain::bail;
 
 pub struct MapGen {
+    startup_player_count: i32,
 }
 
 pub struct MapGenHandle<'a> {
     handle: *mut C4MapgenHandle,
     material_map: &'a MaterialMap,
     texture_map: &'a TextureMap,
+    seed: u32,
+    map_zoom: u32,
+}
+
+/// Which frame of animated textures (like lava) to draw in map images.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AnimationFrame {
+    /// The n-th frame, counting from 0. Wraps around for animations with fewer frames.
+    Frame(usize),
+    /// The average color of all frames.
+    Average,
 }
 
 impl MapGen {
@@ -30,7 +47,7 @@ impl MapGen {
         unsafe {
             c4_mapgen_handle_init_script_engine();
         }
-        let mut mapgen = MapGen {};
+        let mut mapgen = MapGen { startup_player_count: 1 };
         let compat_source = include_str!("StandaloneCompat.c");
         mapgen.load_script("StandaloneCompat.c", compat_source)?;
         Ok(mapgen)
@@ -41,7 +58,28 @@ impl MapGen {
         let objects = Group::open_as_child(&group, "Objects.ocd", false, false)?;
         let libraries = Group::open_as_child(&objects, "Libraries.ocd", false, false)?;
         let map = Group::open_as_child(&libraries, "Map.ocd", false, false)?;
-        unsafe { c4_mapgen_handle_set_map_library(map.handle()); }
+        self.set_map_library(&map)
+    }
+
+    /// Loads Library_Map from the given Map.ocd group.
+    ///
+    /// A Library_Map loaded before is replaced. Other definitions are kept.
+    pub fn set_map_library(&self, group: &Group) -> Result<()> {
+        unsafe { c4_mapgen_handle_set_map_library(group.handle()); }
+        Ok(())
+    }
+
+    /// Loads the script of a single definition from a .ocd group.
+    ///
+    /// Nested definitions are not loaded. A definition with the same ID loaded before is replaced,
+    /// like definitions overload each other in the engine.
+    pub fn load_definition(&mut self, group: &Group, id: &str) -> Result<()> {
+        let loaded = unsafe {
+            c4_mapgen_handle_load_definition(group.handle(), CString::new(id).unwrap().as_ptr())
+        };
+        if !loaded {
+            bail!("couldn't load definition {} from {}", id, group.full_name());
+        }
         Ok(())
     }
 
@@ -64,6 +102,12 @@ impl MapGen {
     /// Sets the result of GetStartupPlayerCount() in `Map.c`.
     pub fn set_startup_player_count(&mut self, count: i32) {
         unsafe { c4_mapgen_handle_set_startup_player_count(count); }
+        self.startup_player_count = count;
+    }
+
+    /// Returns the player count set with `set_startup_player_count`.
+    pub fn startup_player_count(&self) -> i32 {
+        self.startup_player_count
     }
 
     /// Sets the result of GetStartupTeamCount() in `Map.c`.
@@ -72,28 +116,69 @@ impl MapGen {
     }
 
     /// Render a Landscape.txt map.
-    pub fn render_landscape<'a>(&self, filename: &str, source: &str, script_path: &str, material_map: &'a MaterialMap, texture_map: &'a TextureMap, map_width: u32, map_height: u32) -> Result<MapGenHandle<'a>> {
+    ///
+    /// The map size and other settings are taken from the scenario's landscape section, see
+    /// `RenderSettings`.
+    pub fn render_landscape<'a>(&self, filename: &str, source: &str, script: &AlgoScript, material_map: &'a MaterialMap, texture_map: &'a TextureMap, settings: &RenderSettings) -> Result<MapGenHandle<'a>> {
+        let landscape = RawLandscape::new(&settings.landscape);
+        let seed = settings.seed.unwrap_or_else(next_seed);
+        seed_rng(seed);
+        let script_source = script.source.map(|s| CString::new(s).unwrap());
         let mapgen = unsafe {
             MapGenHandle {
                 handle: c4_mapgen_handle_new(
                             CString::new(filename).unwrap().as_ptr(),
                             CString::new(source).unwrap().as_ptr(),
-                            CString::new(script_path).unwrap().as_ptr(),
+                            CString::new(script.path).unwrap().as_ptr(),
+                            script_source.as_ref().map_or(ptr::null(), |s| s.as_ptr()),
                             material_map.handle(),
                             texture_map.handle(),
-                            map_width,
-                            map_height
+                            &landscape.raw,
+                            settings.players
                         ),
                 material_map: material_map,
                 texture_map: texture_map,
+                seed,
+                map_zoom: settings.fixed_map_zoom(),
             }
         };
         mapgen.error()?;
         Ok(mapgen)
     }
 
+    /// Parse a Landscape.txt map with the engine's parser without rendering it.
+    ///
+    /// Returns the outline of the engine's node tree in the format of `LandscapeTxt::outline`.
+    pub fn parse_landscape(&self, filename: &str, source: &str, material_map: &MaterialMap, texture_map: &TextureMap) -> Result<String> {
+        unsafe {
+            let mut error = ptr::null_mut();
+            let outline = c4_mapgen_handle_parse_landscape(
+                              CString::new(filename).unwrap().as_ptr(),
+                              CString::new(source).unwrap().as_ptr(),
+                              material_map.handle(),
+                              texture_map.handle(),
+                              &mut error
+                          );
+            if outline.is_null() {
+                let message = CStr::from_ptr(error).to_string_lossy().into_owned();
+                c4_mapgen_handle_free_string(error);
+                bail!(ErrorKind::MapGen(message));
+            }
+            let result = CStr::from_ptr(outline).to_string_lossy().into_owned();
+            c4_mapgen_handle_free_string(outline);
+            Ok(result)
+        }
+    }
+
     /// Render a Map.c map.
-    pub fn render_script<'a>(&self, filename: &str, source: &str, scenpar: Option<&Scenpar>, material_map: &'a MaterialMap, texture_map: &'a TextureMap, map_width: u32, map_height: u32) -> Result<MapGenHandle<'a>> {
+    ///
+    /// The initial map size is taken from the scenario's landscape section like for
+    /// `render_landscape`. The player count is also the result of GetStartupPlayerCount() during
+    /// this rendering.
+    pub fn render_script<'a>(&self, filename: &str, source: &str, scenpar: Option<&Scenpar>, material_map: &'a MaterialMap, texture_map: &'a TextureMap, settings: &RenderSettings) -> Result<MapGenHandle<'a>> {
+        let landscape = RawLandscape::new(&settings.landscape);
+        let seed = settings.seed.unwrap_or_else(next_seed);
+        seed_rng(seed);
         let mapgen = unsafe {
             MapGenHandle {
                 handle: c4_mapgen_handle_new_script(
@@ -102,11 +187,13 @@ impl MapGen {
                             scenpar.map(|s| s.handle()).unwrap_or_else(|| ptr::null_mut()),
                             material_map.handle(),
                             texture_map.handle(),
-                            map_width,
-                            map_height
+                            &landscape.raw,
+                            settings.players
                         ),
                 material_map: material_map,
                 texture_map: texture_map,
+                seed,
+                map_zoom: settings.fixed_map_zoom(),
             }
         };
         mapgen.error()?;
@@ -114,6 +201,98 @@ impl MapGen {
     }
 }
 
+/// The scenario script for Landscape.txt algo=script overlays, see `MapGen::render_landscape`.
+pub struct AlgoScript<'s> {
+    /// Path of Script.c. Only used in error messages if `source` is given.
+    pub path: &'s str,
+    /// The script, e.g. read from a packed scenario. Loaded from `path` if not given.
+    pub source: Option<&'s str>,
+}
+
+/// Settings for a single render with `MapGen::render_landscape` or `MapGen::render_script`.
+#[derive(Clone, Debug)]
+pub struct RenderSettings {
+    /// The scenario's landscape section. If it has `map_player_extend` set, the map grows in
+    /// width with the player count.
+    pub landscape: Landscape,
+    /// Number of players the map is created for.
+    pub players: i32,
+    /// The RNG is seeded with this right before rendering. Without a seed, one is drawn from the
+    /// global RNG (see `seed_rng`). `MapGenHandle::seed` returns the seed used either way.
+    pub seed: Option<u32>,
+}
+
+impl RenderSettings {
+    /// Returns the MapZoom of the landscape without random deviation, which would take a number
+    /// from the RNG used for the map.
+    fn fixed_map_zoom(&self) -> u32 {
+        let zoom = self.landscape.map_zoom;
+        zoom.standard.max(zoom.min).min(zoom.max).max(1) as u32
+    }
+}
+
+/// Landscape settings converted for the C++ side, keeping the referenced strings alive.
+struct RawLandscape {
+    raw: C4MapgenLandscape,
+    _strings: Vec<CString>,
+    _layer_names: Vec<*const c_char>,
+    _layer_counts: Vec<i32>,
+}
+
+impl RawLandscape {
+    fn new(landscape: &Landscape) -> RawLandscape {
+        let value = |v: ScenarioValue| C4MapgenValue {
+            standard: v.standard,
+            random: v.random,
+            min: v.min,
+            max: v.max,
+        };
+        let sky = landscape.sky.as_ref().map(|s| CString::new(s.as_str()).unwrap());
+        let material = CString::new(landscape.material.as_str()).unwrap();
+        let liquid = CString::new(landscape.liquid.as_str()).unwrap();
+        let layer_strings: Vec<CString> = landscape.layers.iter()
+            .map(|(name, _)| CString::new(name.as_str()).unwrap())
+            .collect();
+        let layer_names: Vec<*const c_char> = layer_strings.iter().map(|s| s.as_ptr()).collect();
+        let layer_counts: Vec<i32> = landscape.layers.iter().map(|&(_, count)| count).collect();
+        let raw = C4MapgenLandscape {
+            map_width: value(landscape.map_width),
+            map_height: value(landscape.map_height),
+            map_zoom: value(landscape.map_zoom),
+            map_player_extend: landscape.map_player_extend as i32,
+            exact_landscape: landscape.exact_landscape,
+            bottom_open: landscape.bottom_open,
+            top_open: landscape.top_open,
+            left_open: landscape.left_open,
+            right_open: landscape.right_open,
+            auto_scan_side_open: landscape.auto_scan_side_open,
+            sky: sky.as_ref().map(|s| s.as_ptr()).unwrap_or(ptr::null()),
+            sky_fade: landscape.sky_fade,
+            amplitude: value(landscape.amplitude),
+            phase: value(landscape.phase),
+            period: value(landscape.period),
+            random: value(landscape.random),
+            liquid_level: value(landscape.liquid_level),
+            gravity: value(landscape.gravity),
+            material: material.as_ptr(),
+            liquid: liquid.as_ptr(),
+            layer_names: layer_names.as_ptr(),
+            layer_counts: layer_counts.as_ptr(),
+            layer_count: layer_names.len(),
+        };
+        // Moving the CStrings and Vecs doesn't move their heap data the pointers refer to.
+        let mut strings = vec![material, liquid];
+        strings.extend(sky);
+        strings.extend(layer_strings);
+        RawLandscape {
+            raw,
+            _strings: strings,
+            _layer_names: layer_names,
+            _layer_counts: layer_counts,
+        }
+    }
+}
+
 impl Drop for MapGen {
     fn drop(&mut self) {
         unsafe {
@@ -134,13 +313,27 @@ impl<'a> MapGenHandle<'a> {
         }
     }
 
-    fn width(&self) -> u32 {
+    /// Returns the seed the RNG was seeded with for this map.
+    ///
+    /// Rendering again with this seed and the same settings gives the same map.
+    pub fn seed(&self) -> u32 {
+        self.seed
+    }
+
+    /// Returns the landscape pixels per map pixel, MapZoom in Scenario.txt (8 per default).
+    pub fn map_zoom(&self) -> u32 {
+        self.map_zoom
+    }
+
+    /// Returns the width of the generated map.
+    pub fn width(&self) -> u32 {
         unsafe {
             c4_mapgen_handle_get_width(self.handle)
         }
     }
 
-    fn height(&self) -> u32 {
+    /// Returns the height of the generated map.
+    pub fn height(&self) -> u32 {
         unsafe {
             c4_mapgen_handle_get_height(self.handle)
         }
@@ -168,23 +361,127 @@ impl<'a> MapGenHandle<'a> {
         }
     }
 
-    /// Returns the foreground map as image.
+    /// Returns the foreground map as image. Animated textures are drawn with their first frame.
     pub fn map_as_image(&self) -> RgbImage {
+        self.map_frame_as_image(AnimationFrame::Frame(0))
+    }
+
+    /// Returns the background map as image. Animated textures are drawn with their first frame.
+    pub fn map_bg_as_image(&self) -> RgbImage {
+        self.map_bg_frame_as_image(AnimationFrame::Frame(0))
+    }
+
+    /// Returns the foreground map as image with the given frame of animated textures.
+    pub fn map_frame_as_image(&self, frame: AnimationFrame) -> RgbImage {
         let width = self.width();
         let height = self.height();
         let data: &[u8] = unsafe { slice::from_raw_parts(c4_mapgen_handle_get_map(self.handle), (width * height) as usize) };
-        self.map_to_image(data)
+        self.map_to_image(data, frame)
     }
 
-    /// Returns the background map as image.
-    pub fn map_bg_as_image(&self) -> RgbImage {
+    /// Returns the background map as image with the given frame of animated textures.
+    pub fn map_bg_frame_as_image(&self, frame: AnimationFrame) -> RgbImage {
         let width = self.width();
         let height = self.height();
         let data: &[u8] = unsafe { slice::from_raw_parts(c4_mapgen_handle_get_bg(self.handle), (width * height) as usize) };
-        self.map_to_image(data)
+        self.map_to_image(data, frame)
+    }
+
+    /// Returns the number of frames of the longest texture animation in the map, 1 if no
+    /// animated material is used.
+    ///
+    /// Drawing frames 0 to `animation_frames() - 1` shows the whole animation of that material.
+    /// Shorter animations repeat in between.
+    pub fn animation_frames(&self) -> usize {
+        let mut used = vec![false; 256];
+        for index in self.map_indices().into_iter().chain(self.map_bg_indices()) {
+            used[index as usize] = true;
+        }
+        (1..256)
+            .filter(|&index| used[index])
+            .map(|index| self.texture_frames(index as u8).len())
+            .max()
+            .unwrap_or(1)
+            .max(1)
+    }
+
+    /// Returns the landscape as the game would create it from the map, with `zoom` landscape
+    /// pixels per map pixel. Without a zoom, the scenario's MapZoom is used (see `map_zoom`).
+    ///
+    /// Materials are drawn with their textures, animated ones with their first frame. Materials
+    /// whose texture has a shape (see `TextureShape`) get the shaped borders of the game instead
+    /// of following the map pixels.
+    pub fn landscape_as_image(&self, zoom: Option<u32>) -> RgbImage {
+        let zoom = zoom.unwrap_or(self.map_zoom);
+        let textures: Vec<Option<PreviewTexture>> = (0..256u32)
+            .map(|index| {
+                if index == 0 {
+                    return None;
+                }
+                let name = self.texture_frames(index as u8).into_iter().next()?.to_lowercase();
+                Some(PreviewTexture {
+                    image: self.texture_map.texture_table.get(&name)?,
+                    shape: self.texture_map.shape_table.get(&name),
+                    min_shape_overlap: self.material(index as u8)
+                        .map_or(0, |mat| mat.properties().min_shape_overlap),
+                })
+            })
+            .collect();
+        preview::render(&self.map_indices(), &self.map_bg_indices(), self.width(), self.height(), zoom, &textures)
+    }
+
+    /// Counts the foreground pixels of each material, most frequent first. Sky is counted as "Sky".
+    pub fn material_counts(&self) -> Vec<(String, u32)> {
+        let mut index_counts = vec![0u32; 256];
+        for index in self.map_indices() {
+            index_counts[index as usize] += 1;
+        }
+        let mut counts: Vec<(String, u32)> = Vec::new();
+        for (index, &count) in index_counts.iter().enumerate().filter(|&(_, &count)| count > 0) {
+            let name = if index == 0 {
+                "Sky".to_owned()
+            } else {
+                self.texture_map.get_material_name(index as u32).unwrap_or_else(|| format!("#{}", index))
+            };
+            // Each material may be mapped with several textures.
+            match counts.iter_mut().find(|(n, _)| *n == name) {
+                Some(entry) => entry.1 += count,
+                None => counts.push((name, count)),
+            }
+        }
+        counts.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+        counts
+    }
+
+    /// Returns the material of a texture map index as found in `map_indices`, `None` for sky.
+    pub fn material(&self, index: u8) -> Option<Material> {
+        self.texture_map.get_material_name(index as u32)
+            .and_then(|name| self.material_map.get_material_by_name(&name))
+    }
+
+    /// Returns the foreground map as texture map indices, one byte per pixel.
+    pub fn map_indices(&self) -> Vec<u8> {
+        let data: &[u8] = unsafe { slice::from_raw_parts(c4_mapgen_handle_get_map(self.handle), (self.rowstride() * self.height()) as usize) };
+        self.compact_rows(data)
     }
 
-    fn map_to_image(&self, data: &[u8]) -> RgbImage {
+    /// Returns the background map as texture map indices, one byte per pixel.
+    pub fn map_bg_indices(&self) -> Vec<u8> {
+        let data: &[u8] = unsafe { slice::from_raw_parts(c4_mapgen_handle_get_bg(self.handle), (self.rowstride() * self.height()) as usize) };
+        self.compact_rows(data)
+    }
+
+    /// Removes row padding from a map buffer.
+    fn compact_rows(&self, data: &[u8]) -> Vec<u8> {
+        let width = self.width() as usize;
+        let rowstride = self.rowstride() as usize;
+        data.chunks(rowstride)
+            .take(self.height() as usize)
+            .flat_map(|row| row[..width].iter().cloned())
+            .collect()
+    }
+
+    fn map_to_image(&self, data: &[u8], frame: AnimationFrame) -> RgbImage {
         let width = self.width();
         let height = self.height();
         let rowstride = self.rowstride();
@@ -195,40 +492,50 @@ impl<'a> MapGenHandle<'a> {
             if let Some(color) = mat_colors[mat_idx as usize] {
                 color
             } else {
-                let color = self.get_mat_color(mat_idx);
+                let color = self.get_mat_color(mat_idx, frame);
                 mat_colors[mat_idx as usize] = Some(color);
                 color
             }
         })
     }
 
-    fn get_mat_color(&self, mat_idx: u8) -> image::Rgb<u8> {
+    fn get_mat_color(&self, mat_idx: u8, frame: AnimationFrame) -> image::Rgb<u8> {
         const SKY: image::Rgb<u8> = image::Rgb([100, 100, 255]);
-        if mat_idx == 0 {
-            SKY
+        let frames = self.texture_frames(mat_idx);
+        // This really shouldn't happen and I think mape doesn't even handle this case.
+        if mat_idx == 0 || frames.is_empty() {
+            return SKY;
+        }
+        let colors: Vec<[u32; 3]> = match frame {
+            AnimationFrame::Frame(n) => vec![&frames[n % frames.len()]],
+            AnimationFrame::Average => frames.iter().collect(),
+        }.into_iter()
+            .map(|name| {
+                let color = self.texture_map.get_average_texture_color(name);
+                [(color >> 16) & 0xff, (color >> 8) & 0xff, color & 0xff]
+            })
+            .collect();
+        let n = colors.len() as u32;
+        let sum = colors.iter().fold([0; 3], |s, c| [s[0] + c[0], s[1] + c[1], s[2] + c[2]]);
+        image::Rgb([(sum[0] / n) as u8, (sum[1] / n) as u8, (sum[2] / n) as u8])
+    }
+
+    /// Returns the names of the loaded textures to draw a texture map entry with, one for each
+    /// animation frame.
+    fn texture_frames(&self, mat_idx: u8) -> Vec<String> {
+        let texture_name = match self.texture_map.get_texture_name(mat_idx as u32) {
+            Some(name) => name,
+            None => return Vec::new(),
+        };
+        // Animated textures consist of several textures separated with '-' characters.
+        let frames: Vec<String> = texture_name.split('-')
+            .filter(|name| self.texture_map.texture_table.contains_key(&name.to_lowercase()))
+            .map(|name| name.to_owned())
+            .collect();
+        if frames.is_empty() {
+            self.material(mat_idx).map(|mat| mat.texture_overlay()).into_iter().collect()
         } else {
-            if let Some(texture_name) = self.texture_map.get_texture_name(mat_idx as u32) {
-                // Comment powered by mape source:
-                /* When the texture is animated, the texture name consists of more than
-                 * one texture, separated with a '-' character. In this case, we simply
-                 * use the first one for display. */
-                let texture_name = texture_name.split("-").next().unwrap();
-                let texture_name = if !self.texture_map.texture_table.contains_key(&texture_name.to_lowercase()) {
-                    match self.texture_map.get_material_name(mat_idx as u32)
-                              .and_then(|name| self.material_map.get_material_by_name(&name))
-                              .and_then(|mat| Some(mat.texture_overlay())) {
-                        Some(name) => name,
-                        None => { return SKY; }
-                    }
-                } else { texture_name.to_owned() };
-                let color = self.texture_map.get_average_texture_color(&texture_name);
-                image::Rgb([((color >> 16) & 0xff) as u8,
-                             ((color >> 8) & 0xff) as u8,
-                             (color & 0xff) as u8])
-            } else {
-                // This really shouldn't happen and I think mape doesn't even handle this case.
-                SKY
-            }
+            frames
         }
     }
 
@@ -259,6 +566,56 @@ impl<'a> MapGenHandle<'a> {
         }
         Ok(())
     }
+
+    /// Writes the map into a scenario as `Map.bmp` and `MapBg.bmp` and saves the group.
+    ///
+    /// With `static_map`, the scenario is changed to always use this map: Map.c is removed as
+    /// the engine would run it on top of the static map, and the `[Landscape]` section of
+    /// Scenario.txt gets the fixed map size with ExactLandscape and MapPlayerExtend turned off.
+    /// The rest of Scenario.txt is kept byte for byte. Nothing is changed if it can't be read.
+    pub fn export_to_scenario(&self, group: &mut Group, static_map: bool) -> Result<()> {
+        let mut scenario_txt = None;
+        let mut has_map_c = false;
+        if static_map {
+            // Only a missing Scenario.txt is fine, it is created then.
+            let source = if group.find("Scenario.txt")?.is_empty() {
+                Vec::new()
+            } else {
+                group.load_entry("Scenario.txt")?
+            };
+            let (width, height) = (self.width(), self.height());
+            scenario_txt = Some(set_entries(&source, "Landscape", &[
+                ("MapWidth", format!("{},0,{},{}", width, width, width)),
+                ("MapHeight", format!("{},0,{},{}", height, height, height)),
+                ("MapPlayerExtend", "0".to_owned()),
+                ("ExactLandscape", "0".to_owned()),
+            ]));
+            has_map_c = !group.find("Map.c")?.is_empty();
+        }
+        group.add_entry("Map.bmp", &self.map_as_bmp(false))?;
+        group.add_entry("MapBg.bmp", &self.map_as_bmp(true))?;
+        if let Some(scenario_txt) = scenario_txt {
+            group.add_entry("Scenario.txt", &scenario_txt)?;
+        }
+        if has_map_c {
+            group.delete_entries("Map.c", false)?;
+        }
+        group.save()
+    }
+
+    /// Returns the map or map bg as indexed bmp, see `save_map`.
+    fn map_as_bmp(&self, bg: bool) -> Vec<u8> {
+        unsafe {
+            let mut size = 0;
+            let data = c4_mapgen_handle_get_map_bmp(
+                self.handle,
+                bg,
+                &mut size,
+                self.material_map.handle(),
+                self.texture_map.handle());
+            slice::from_raw_parts(data, size).to_vec()
+        }
+    }
 }
 
 unsafe fn char_to_maybe_string(string: *const ::std::os::raw::c_char) -> Option<String> {
@@ -0,0 +1,153 @@
+//! Synthetic material sets for rendering without the OpenClonk game data.
+//!
+//! A `MaterialSet` collects materials, textures and texture map entries and writes them as a
+//! Material.ocg group into an `AssetBundle`. Add that group with `AssetConfig::material` and load
+//! it with `Easy::load_assets` like any other. Landscape.txt maps and Map.c scripts not using
+//! Library_Map render without further assets.
+
+use crate::errors::*;
+use crate::{AssetBundle, MaterialProperties};
+
+use std::io::Cursor;
+use std::path::PathBuf;
+
+use error_chain::bail;
+use image::{DynamicImage, ImageOutputFormat};
+
+/// Materials, textures and texture map entries to write as a Material.ocg group.
+#[derive(Clone, Default)]
+pub struct MaterialSet {
+    materials: Vec<(String, MaterialProperties)>,
+    textures: Vec<(String, DynamicImage)>,
+    /// Texture shapes by texture name.
+    shapes: Vec<(String, DynamicImage)>,
+    /// Material and texture name of each entry, starting at index 1.
+    entries: Vec<(String, String)>,
+}
+
+impl MaterialSet {
+    pub fn new() -> MaterialSet {
+        MaterialSet::default()
+    }
+
+    /// Adds a material, replacing one with the same name.
+    pub fn material(&mut self, name: &str, properties: MaterialProperties) -> &mut MaterialSet {
+        self.materials.retain(|(n, _)| n != name);
+        self.materials.push((name.to_owned(), properties));
+        self
+    }
+
+    /// Adds a texture, replacing one with the same name. The map preview uses its average color.
+    pub fn texture(&mut self, name: &str, image: DynamicImage) -> &mut MaterialSet {
+        self.textures.retain(|(n, _)| n != name);
+        self.textures.push((name.to_owned(), image));
+        self
+    }
+
+    /// Adds a shape for a texture, see `TextureShape`.
+    pub fn texture_shape(&mut self, texture: &str, image: DynamicImage) -> &mut MaterialSet {
+        self.shapes.retain(|(n, _)| n != texture);
+        self.shapes.push((texture.to_owned(), image));
+        self
+    }
+
+    /// Adds a texture map entry, which makes `Material-texture` usable in maps.
+    ///
+    /// Entries get texture map indices in the order they are added, starting at 1. The first
+    /// entry of a material is its default texture.
+    pub fn entry(&mut self, material: &str, texture: &str) -> &mut MaterialSet {
+        self.entries.push((material.to_owned(), texture.to_owned()));
+        self
+    }
+
+    /// Writes the set as an unpacked `Material.ocg` into the bundle and returns its path.
+    pub fn write_to(&self, bundle: &mut AssetBundle) -> Result<PathBuf> {
+        self.check()?;
+        for (name, properties) in &self.materials {
+            bundle.add_file(&format!("Material.ocg/{}.ocm", name), ocm(name, properties).as_bytes())?;
+        }
+        for (name, image) in &self.textures {
+            bundle.add_file(&format!("Material.ocg/{}.png", name), &png(image, name)?)?;
+        }
+        for (name, image) in &self.shapes {
+            bundle.add_file(&format!("Material.ocg/{}Shape.png", name), &png(image, name)?)?;
+        }
+        bundle.add_file("Material.ocg/TexMap.txt", self.texmap().as_bytes())?;
+        Ok(bundle.path().join("Material.ocg"))
+    }
+
+    /// Checks that names are usable as file names and entries refer to known names.
+    fn check(&self) -> Result<()> {
+        if self.entries.len() > 255 {
+            bail!("too many texture map entries ({}, at most 255)", self.entries.len());
+        }
+        let names = self.materials.iter().map(|(n, _)| n).chain(self.textures.iter().map(|(n, _)| n));
+        for name in names {
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                bail!("invalid material or texture name '{}'", name);
+            }
+        }
+        if let Some((name, _)) = self.shapes.iter().find(|(n, _)| !self.textures.iter().any(|(t, _)| t == n)) {
+            bail!("texture shape for unknown texture {}", name);
+        }
+        for (material, texture) in &self.entries {
+            if !self.materials.iter().any(|(n, _)| n == material) {
+                bail!("texture map entry {}-{} refers to unknown material", material, texture);
+            }
+            if !self.textures.iter().any(|(n, _)| n == texture) {
+                bail!("texture map entry {}-{} refers to unknown texture", material, texture);
+            }
+        }
+        Ok(())
+    }
+
+    fn texmap(&self) -> String {
+        self.entries.iter().enumerate()
+            .map(|(i, (material, texture))| format!("{}={}-{}\n", i + 1, material, texture))
+            .collect()
+    }
+}
+
+fn png(image: &DynamicImage, name: &str) -> Result<Vec<u8>> {
+    let mut png = Cursor::new(Vec::new());
+    image.write_to(&mut png, ImageOutputFormat::Png)
+        .chain_err(|| format!("couldn't encode image for {}", name))?;
+    Ok(png.into_inner())
+}
+
+/// Returns the .ocm file for a material.
+fn ocm(name: &str, p: &MaterialProperties) -> String {
+    let flag = |b: bool| b as i32;
+    let rgb = |c: [i32; 3]| format!("{},{},{}", c[0], c[1], c[2]);
+    format!("[Material]\nName={}\nDensity={}\nFriction={}\nDigFree={}\nBlastFree={}\nInstable={}\n\
+             Placement={}\nIncendiary={}\nExtinguisher={}\nLightAngle={}\nLightEmit={}\nLightSpot={}\n\
+             MinShapeOverlap={}\n",
+            name, p.density, p.friction, flag(p.dig_free), flag(p.blast_free), flag(p.instable),
+            p.placement, flag(p.incendiary), flag(p.extinguisher), p.light_angle,
+            rgb(p.light_emit), rgb(p.light_spot), p.min_shape_overlap)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MaterialSet;
+    use crate::MaterialProperties;
+
+    use image::{DynamicImage, RgbImage};
+
+    #[test]
+    fn texmap() {
+        let earth = MaterialProperties { density: 50, dig_free: true, ..Default::default() };
+        let mut set = MaterialSet::new();
+        set.material("Earth", earth)
+           .material("Tunnel", MaterialProperties::default())
+           .texture("earth", DynamicImage::ImageRgb8(RgbImage::new(4, 4)))
+           .entry("Earth", "earth")
+           .entry("Tunnel", "earth");
+        assert!(set.check().is_ok());
+        assert_eq!(set.texmap(), "1=Earth-earth\n2=Tunnel-earth\n");
+        assert!(super::ocm("Earth", &earth).contains("\nDensity=50\nFriction=0\nDigFree=1\n"));
+
+        set.entry("Water", "earth");
+        assert!(set.check().is_err());
+    }
+}
@@ -2,8 +2,10 @@ use crate::ffi::*;
 use crate::errors::*;
 use crate::Handle;
 use crate::group::Group;
+use crate::preview::TextureShape;
 use std::ffi::{CStr, CString};
 use std::collections::HashMap;
+use std::mem;
 
 use image::{self, DynamicImage};
 use error_chain::bail;
@@ -35,11 +37,22 @@ impl MaterialMap {
         }
     }
 
+    /// Looks up a material by its name, ignoring case.
     pub fn get_material_by_name(&self, name: &str) -> Option<Material> {
-        let lname = name.to_lowercase();
+        let index = unsafe {
+            c4_material_map_handle_get_index(self.handle, CString::new(name).unwrap().as_ptr())
+        };
+        if index < 0 {
+            None
+        } else {
+            self.get_material_by_index(index as u32)
+        }
+    }
+
+    /// Iterates over all materials with their index, in loading order.
+    pub fn materials(&self) -> impl Iterator<Item = (u32, Material)> + '_ {
         (0 .. unsafe { c4_material_map_handle_get_num(self.handle) })
-            .filter_map(|i| self.get_material_by_index(i))
-            .find(|ref mat| mat.name().to_lowercase() == lname)
+            .filter_map(move |i| self.get_material_by_index(i).map(|mat| (i, mat)))
     }
 
     pub fn get_material_by_index(&self, index: u32) -> Option<Material> {
@@ -88,11 +101,122 @@ impl Material {
                 .into_owned()
         }
     }
+
+    /// Returns the properties from the material's .ocm file relevant for the map.
+    pub fn properties(&self) -> MaterialProperties {
+        let raw = unsafe {
+            let mut raw: C4MaterialProperties = mem::zeroed();
+            c4_material_handle_get_properties(self.handle, &mut raw);
+            raw
+        };
+        MaterialProperties {
+            density: raw.density,
+            friction: raw.friction,
+            dig_free: raw.dig_free != 0,
+            blast_free: raw.blast_free != 0,
+            instable: raw.instable != 0,
+            placement: raw.placement,
+            incendiary: raw.incendiary != 0,
+            extinguisher: raw.extinguisher != 0,
+            light_angle: raw.light_angle,
+            light_emit: raw.light_emit,
+            light_spot: raw.light_spot,
+            min_shape_overlap: raw.min_shape_overlap,
+        }
+    }
+
+    /// Returns the background material the engine puts behind this material if the map doesn't
+    /// specify one: Tunnel behind liquids and solids, the material itself otherwise.
+    pub fn default_bg_material(&self) -> String {
+        if self.properties().is_semi_solid() {
+            "Tunnel".to_owned()
+        } else {
+            self.name()
+        }
+    }
+}
+
+/// Density from which on a material is liquid (`C4M_Liquid` in the engine).
+const DENSITY_LIQUID: i32 = 25;
+/// Density from which on a material is solid (`C4M_Solid` in the engine).
+const DENSITY_SOLID: i32 = 50;
+
+/// Material properties as set in the .ocm file, see `Material::properties`.
+///
+/// The default is a passable material like Tunnel.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MaterialProperties {
+    /// 0 for background materials like Tunnel, 25 and up for liquids, 50 and up for solids.
+    pub density: i32,
+    pub friction: i32,
+    /// Whether the material can be dug out.
+    pub dig_free: bool,
+    /// Whether the material resists explosions.
+    pub blast_free: bool,
+    /// Whether the material crumbles down when not supported, like sand.
+    pub instable: bool,
+    /// Drawing order at material borders, higher values are drawn on top.
+    pub placement: i32,
+    /// Whether the material sets objects on fire, like lava.
+    pub incendiary: bool,
+    /// Whether the material extinguishes burning objects, like water.
+    pub extinguisher: bool,
+    /// Light settings for the landscape lighting (LightAngle, LightEmit and LightSpot).
+    pub light_angle: i32,
+    pub light_emit: [i32; 3],
+    pub light_spot: [i32; 3],
+    /// Percentage of a texture shape the material has to cover for the shape to be drawn, see
+    /// `TextureShape`.
+    pub min_shape_overlap: i32,
+}
+
+impl MaterialProperties {
+    pub fn is_liquid(&self) -> bool {
+        self.density >= DENSITY_LIQUID && self.density < DENSITY_SOLID
+    }
+
+    pub fn is_solid(&self) -> bool {
+        self.density >= DENSITY_SOLID
+    }
+
+    /// Whether the material is liquid or solid, i.e. not passable like Tunnel.
+    pub fn is_semi_solid(&self) -> bool {
+        self.density >= DENSITY_LIQUID
+    }
+
+    /// Whether the material emits light.
+    pub fn is_light_source(&self) -> bool {
+        self.light_emit.iter().any(|&c| c > 0)
+    }
 }
 
 pub struct TextureMap {
     handle: *mut C4TextureMapHandle,
     pub texture_table: HashMap<String, DynamicImage>,
+    /// Texture shapes by lowercase texture name.
+    pub shape_table: HashMap<String, TextureShape>,
+}
+
+/// Number of indices in the texture map, including 0 for sky.
+const TEXTURE_MAP_SIZE: u32 = 256;
+
+/// A material-texture combination from TexMap.txt, see `TextureMap::entries`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextureMapEntry {
+    /// Index as used in the map, see `MapGenHandle::map_indices`.
+    pub index: u8,
+    pub material: String,
+    pub texture: String,
+    /// Whether the entry can be drawn into the background map. The engine doesn't allow
+    /// liquids in the background.
+    pub background: bool,
+}
+
+impl TextureMapEntry {
+    /// Returns the `Material-Texture` name used e.g. by `Draw` in Map.c.
+    pub fn name(&self) -> String {
+        format!("{}-{}", self.material, self.texture)
+    }
 }
 
 pub struct LoadTextureMapResult {
@@ -107,6 +231,7 @@ impl TextureMap {
             TextureMap {
                 handle: c4_texture_map_handle_new(),
                 texture_table: HashMap::new(),
+                shape_table: HashMap::new(),
             }
         }
     }
@@ -133,22 +258,36 @@ impl TextureMap {
     }
 
     pub fn load_textures(&mut self, group: &mut Group) -> Result<()> {
-        group.rewind();
+        // Shapes for textures are stored next to them as <texture>Shape.png, named exactly like
+        // the engine expects them.
+        const SHAPE_SUFFIX: &str = "Shape.png";
         const SUFFIXES: &'static [&'static str] = &[
             ".png", ".jpg", ".jpeg", ".bmp",
             ".PNG", ".JPG", ".JPEG", ".BMP"
         ];
-        while let Some(name) = group.next() {
+        // Names that aren't valid UTF-8 are looked up by their lossy name, which is what
+        // `get_texture_name` returns for them as well. The engine gets the raw name, as used
+        // in TexMap.txt.
+        let mut shapes = Vec::new();
+        for entry in group.iter_entries("*") {
+            let entry = entry?;
+            let name = &entry.name;
+            // Shapes are loaded after all textures, as they belong to one.
+            if name.ends_with(SHAPE_SUFFIX) {
+                shapes.push(entry);
+                continue;
+            }
             if let Some(suffix) = SUFFIXES.iter().find(|&suffix| name.ends_with(suffix)) {
                 let texname = &name[0 .. (name.len() - suffix.len())];
                 let lowercase_name = texname.to_lowercase();
                 if self.texture_table.get(&lowercase_name).is_none() {
-                    let data = group.load_entry(&name)?;
+                    let data = group.load_entry(&entry.raw_name)?;
                     let image = image::load_from_memory(&data)
                                 .chain_err(|| "could not load texture image")?;
                     let avgcolor = get_average_color(&image);
                     unsafe {
-                        let s = CString::new(texname).unwrap();
+                        let raw_texname = &entry.raw_name[0 .. (entry.raw_name.len() - suffix.len())];
+                        let s = CString::new(raw_texname).unwrap();
                         if !c4_texture_map_handle_add_texture(self.handle, s.as_ptr(), avgcolor) {
                             bail!("failed adding texture {}", texname);
                         }
@@ -157,6 +296,20 @@ impl TextureMap {
                 }
             }
         }
+        for entry in shapes {
+            let name = &entry.name;
+            let texname = name[0 .. (name.len() - SHAPE_SUFFIX.len())].to_lowercase();
+            // Like the engine, ignore shapes without texture.
+            if self.shape_table.contains_key(&texname) || !self.texture_table.contains_key(&texname) {
+                continue;
+            }
+            let data = group.load_entry(&entry.raw_name)?;
+            let shape = image::load_from_memory(&data)
+                        .chain_err(|| "could not load texture shape image")
+                        .and_then(|image| TextureShape::from_image(&image))
+                        .chain_err(|| format!("invalid texture shape {}", name))?;
+            self.shape_table.insert(texname, shape);
+        }
         Ok(())
     }
 
@@ -186,6 +339,19 @@ impl TextureMap {
         }
     }
 
+    /// Iterates over the entries of TexMap.txt, ordered by index.
+    ///
+    /// The material map is needed to tell which entries are usable for the background.
+    pub fn entries<'a>(&'a self, material_map: &'a MaterialMap) -> impl Iterator<Item = TextureMapEntry> + 'a {
+        (0 .. TEXTURE_MAP_SIZE).filter_map(move |index| {
+            let material = self.get_material_name(index).filter(|m| !m.is_empty())?;
+            let texture = self.get_texture_name(index).unwrap_or_default();
+            let background = material_map.get_material_by_name(&material)
+                .is_some_and(|mat| !mat.properties().is_liquid());
+            Some(TextureMapEntry { index: index as u8, material, texture, background })
+        })
+    }
+
     pub fn get_average_texture_color(&self, name: &str) -> u32 {
         unsafe {
             c4_texture_handle_get_average_texture_color(self.handle, CString::new(name).unwrap().as_ptr())
@@ -2,12 +2,22 @@ use crate::ffi::*;
 use std::ffi::CStr;
 
 /// Seeds the RNG used for map generation.
+///
+/// Renders without a fixed seed draw their seed from this RNG, so seeding it makes a series of
+/// renders repeatable.
 pub fn seed_rng(seed: u32) {
     unsafe {
         c4_random_handle_seed(seed);
     }
 }
 
+/// Draws a seed for a render from the global RNG.
+pub(crate) fn next_seed() -> u32 {
+    unsafe {
+        c4_random_handle_next_seed()
+    }
+}
+
 /// Returns the version of the linked OpenClonk library.
 pub fn openclonk_version() -> String {
     unsafe {
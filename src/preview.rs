@@ -0,0 +1,156 @@
+//! Textured landscape previews, see `MapGenHandle::landscape_as_image`.
+
+use crate::errors::*;
+
+use std::collections::HashMap;
+
+use error_chain::bail;
+use image::{DynamicImage, GenericImageView, Rgb, RgbImage};
+
+/// Divides a texture into shapes like single rocks or bricks (`<texture>Shape.png`).
+///
+/// Each color in the shape image is one shape, transparent pixels don't belong to any. When the
+/// landscape is drawn, shapes overlapping a material enough are drawn as a whole, so the material
+/// gets a shaped border instead of following the blocky map pixels.
+pub struct TextureShape {
+    width: u32,
+    height: u32,
+    /// Shape number for each pixel.
+    pixels: Vec<Option<u16>>,
+}
+
+impl TextureShape {
+    pub fn from_image(image: &DynamicImage) -> Result<TextureShape> {
+        let (width, height) = image.dimensions();
+        if width == 0 || height == 0 {
+            bail!("empty texture shape");
+        }
+        let mut colors = HashMap::new();
+        let mut pixels = Vec::with_capacity((width * height) as usize);
+        for (_, _, px) in image.pixels() {
+            if px[3] == 0 {
+                pixels.push(None);
+                continue;
+            }
+            let next = colors.len();
+            let shape = *colors.entry([px[0], px[1], px[2]]).or_insert(next);
+            if shape > u16::MAX as usize {
+                bail!("too many shapes in texture shape");
+            }
+            pixels.push(Some(shape as u16));
+        }
+        Ok(TextureShape { width, height, pixels })
+    }
+
+    /// Returns the shape at a landscape position, tiling the shape image.
+    fn shape_at(&self, x: u32, y: u32) -> Option<u16> {
+        self.pixels[((y % self.height) * self.width + x % self.width) as usize]
+    }
+}
+
+/// A texture map entry as needed for drawing.
+pub(crate) struct PreviewTexture<'a> {
+    pub image: &'a DynamicImage,
+    pub shape: Option<&'a TextureShape>,
+    /// Percentage of a shape that has to be covered by the material to draw it.
+    pub min_shape_overlap: i32,
+}
+
+const SKY: Rgb<u8> = Rgb([100, 100, 255]);
+
+/// Draws the landscape for the given foreground and background texture map indices.
+///
+/// Each map pixel becomes a `zoom`×`zoom` block textured like in the game. Pixels of a material
+/// with texture shape but outside of its drawn shapes show the background.
+///
+/// Unlike the engine, shapes crossing the border of the shape image are treated as two shapes.
+pub(crate) fn render(fg: &[u8], bg: &[u8], width: u32, height: u32, zoom: u32, textures: &[Option<PreviewTexture>]) -> RgbImage {
+    let zoom = zoom.max(1);
+    let index_at = |map: &[u8], x: u32, y: u32| map[((y / zoom) * width + x / zoom) as usize];
+    let color = |index: u8, x: u32, y: u32| match textures[index as usize] {
+        Some(ref tex) => {
+            let (tw, th) = tex.image.dimensions();
+            let px = tex.image.get_pixel(x % tw, y % th);
+            Rgb([px[0], px[1], px[2]])
+        },
+        None => SKY,
+    };
+    let shaped = |index: u8| index != 0 && textures[index as usize].as_ref().is_some_and(|t| t.shape.is_some());
+
+    let mut img = RgbImage::from_fn(width * zoom, height * zoom, |x, y| {
+        let fg = index_at(fg, x, y);
+        if fg != 0 && !shaped(fg) {
+            color(fg, x, y)
+        } else {
+            color(index_at(bg, x, y), x, y)
+        }
+    });
+
+    let mut shaped_indices: Vec<u8> = fg.iter().cloned().filter(|&i| shaped(i)).collect();
+    shaped_indices.sort_unstable();
+    shaped_indices.dedup();
+    for index in shaped_indices {
+        let tex = textures[index as usize].as_ref().unwrap();
+        let shape = tex.shape.unwrap();
+        // Covered and total pixels of each shape instance, keyed by tile and shape number.
+        let mut overlap: HashMap<(u32, u32, u16), (u32, u32)> = HashMap::new();
+        for y in 0..img.height() {
+            for x in 0..img.width() {
+                if let Some(s) = shape.shape_at(x, y) {
+                    let counts = overlap.entry((x / shape.width, y / shape.height, s)).or_insert((0, 0));
+                    counts.1 += 1;
+                    if index_at(fg, x, y) == index {
+                        counts.0 += 1;
+                    }
+                }
+            }
+        }
+        for y in 0..img.height() {
+            for x in 0..img.width() {
+                if let Some(s) = shape.shape_at(x, y) {
+                    let (covered, total) = overlap[&(x / shape.width, y / shape.height, s)];
+                    if covered > 0 && covered as i64 * 100 >= tex.min_shape_overlap as i64 * total as i64 {
+                        img.put_pixel(x, y, color(index, x, y));
+                    }
+                }
+            }
+        }
+    }
+    img
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render, PreviewTexture, TextureShape};
+
+    use image::{DynamicImage, Rgb, RgbImage, RgbaImage, Rgba};
+
+    #[test]
+    fn shapes() {
+        // A 4x4 shape image with two shapes, left and right half.
+        let shape_image = RgbaImage::from_fn(4, 4, |x, _| if x < 2 { Rgba([255, 0, 0, 255]) } else { Rgba([0, 255, 0, 255]) });
+        let shape = TextureShape::from_image(&DynamicImage::ImageRgba8(shape_image)).unwrap();
+        let rock = DynamicImage::ImageRgb8(RgbImage::from_pixel(1, 1, Rgb([200, 0, 0])));
+        let tunnel = DynamicImage::ImageRgb8(RgbImage::from_pixel(1, 1, Rgb([0, 0, 0])));
+        let textures = vec![
+            None,
+            Some(PreviewTexture { image: &rock, shape: Some(&shape), min_shape_overlap: 50 }),
+            Some(PreviewTexture { image: &tunnel, shape: None, min_shape_overlap: 0 }),
+        ];
+        // Map of 4x1 pixels zoomed by 1: rock covers three pixels of the 4 pixel wide shape tile,
+        // so the left shape (2/2) and the right shape (1/2) are drawn.
+        let fg = [1, 1, 1, 0];
+        let bg = [2, 2, 2, 2];
+        let img = render(&fg, &bg, 4, 1, 1, &textures);
+        assert_eq!(img.get_pixel(3, 0), &Rgb([200, 0, 0]));
+        // With a higher required overlap, the right shape is left out and shows the background.
+        let textures = vec![
+            None,
+            Some(PreviewTexture { image: &rock, shape: Some(&shape), min_shape_overlap: 75 }),
+            Some(PreviewTexture { image: &tunnel, shape: None, min_shape_overlap: 0 }),
+        ];
+        let img = render(&fg, &bg, 4, 1, 1, &textures);
+        assert_eq!(img.get_pixel(1, 0), &Rgb([200, 0, 0]));
+        assert_eq!(img.get_pixel(2, 0), &Rgb([0, 0, 0]));
+    }
+}
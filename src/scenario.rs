@@ -0,0 +1,329 @@
+use crate::errors::*;
+use crate::easy::MapType;
+use crate::group::Group;
+use crate::ini::IniFile;
+use crate::scenpar::Scenpar;
+
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use error_chain::bail;
+
+/// Maximum number of players the map grows for with MapPlayerExtend, as in the engine.
+const MAX_MAP_PLAYER_EXTEND: u32 = 4;
+
+/// A randomizable value from Scenario.txt (`C4SVal` in the engine), written as
+/// `standard,random,min,max`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScenarioValue {
+    pub standard: i32,
+    pub random: i32,
+    pub min: i32,
+    pub max: i32,
+}
+
+impl ScenarioValue {
+    pub fn new(standard: i32, random: i32, min: i32, max: i32) -> ScenarioValue {
+        ScenarioValue { standard, random, min, max }
+    }
+
+    /// Parses a value, keeping the defaults for omitted components like the engine does.
+    fn parse(s: &str, default: ScenarioValue) -> Result<ScenarioValue> {
+        let mut parts = [default.standard, default.random, default.min, default.max];
+        for (i, part) in s.split(',').enumerate() {
+            if i >= parts.len() {
+                bail!("too many components in '{}'", s);
+            }
+            parts[i] = part.trim().parse()
+                .chain_err(|| format!("invalid value '{}'", s))?;
+        }
+        Ok(ScenarioValue::new(parts[0], parts[1], parts[2], parts[3]))
+    }
+}
+
+/// Parses `standard[,random[,min[,max]]]`. Omitted bounds don't restrict the value.
+impl FromStr for ScenarioValue {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<ScenarioValue> {
+        ScenarioValue::parse(s, ScenarioValue::new(0, 0, 1, i32::MAX))
+    }
+}
+
+/// The `[Landscape]` section of Scenario.txt (`C4SLandscape` in the engine).
+///
+/// Only the map generator looks at these values, so settings only relevant for the running game
+/// (vegetation, objects in earth, ...) are left out.
+#[derive(Clone, Debug)]
+pub struct Landscape {
+    pub map_width: ScenarioValue,
+    pub map_height: ScenarioValue,
+    /// Landscape pixels per map pixel.
+    pub map_zoom: ScenarioValue,
+    /// Whether the map grows in width with the number of players.
+    pub map_player_extend: bool,
+    /// Whether Map.bmp is a full-size landscape instead of a map.
+    pub exact_landscape: bool,
+    pub bottom_open: bool,
+    pub top_open: bool,
+    /// Height of the open part of the left side, in landscape pixels.
+    pub left_open: i32,
+    /// Height of the open part of the right side, in landscape pixels.
+    pub right_open: i32,
+    pub auto_scan_side_open: bool,
+    /// Sky definition or image, the default sky if not set.
+    pub sky: Option<String>,
+    /// Sky gradient colors (two RGB triples) if there is no sky image.
+    pub sky_fade: [i32; 6],
+    pub amplitude: ScenarioValue,
+    pub phase: ScenarioValue,
+    pub period: ScenarioValue,
+    pub random: ScenarioValue,
+    pub liquid_level: ScenarioValue,
+    pub gravity: ScenarioValue,
+    /// Default material of the classic map creator.
+    pub material: String,
+    /// Default liquid of the classic map creator.
+    pub liquid: String,
+    /// Material layers of the classic map creator with their amount.
+    pub layers: Vec<(String, i32)>,
+}
+
+impl Default for Landscape {
+    fn default() -> Landscape {
+        Landscape {
+            map_width: ScenarioValue::new(100, 0, 64, 250),
+            map_height: ScenarioValue::new(50, 0, 40, 250),
+            map_zoom: ScenarioValue::new(8, 0, 5, 15),
+            map_player_extend: false,
+            exact_landscape: false,
+            bottom_open: false,
+            top_open: true,
+            left_open: 0,
+            right_open: 0,
+            auto_scan_side_open: true,
+            sky: None,
+            sky_fade: [0; 6],
+            amplitude: ScenarioValue::new(0, 0, 0, 100),
+            phase: ScenarioValue::new(50, 0, 0, 100),
+            period: ScenarioValue::new(15, 0, 0, 100),
+            random: ScenarioValue::new(0, 0, 0, 100),
+            liquid_level: ScenarioValue::new(0, 0, 0, 100),
+            gravity: ScenarioValue::new(100, 0, 10, 200),
+            material: "Earth".into(),
+            liquid: "Water".into(),
+            layers: Vec::new(),
+        }
+    }
+}
+
+impl Landscape {
+    /// Returns the default landscape with a fixed map size.
+    ///
+    /// The maximum width leaves room for growing with the player count if `map_player_extend` is
+    /// enabled afterwards.
+    pub fn with_map_size(width: u32, height: u32) -> Landscape {
+        let clamp = |size: u32| size.min(i32::MAX as u32) as i32;
+        let (width, height) = (clamp(width), clamp(height));
+        Landscape {
+            map_width: ScenarioValue::new(width, 0, width, width.saturating_mul(MAX_MAP_PLAYER_EXTEND as i32)),
+            map_height: ScenarioValue::new(height, 0, height, height),
+            ..Landscape::default()
+        }
+    }
+
+    /// Parses the `[Landscape]` section of a Scenario.txt. Missing entries keep their default.
+    pub fn parse(scenario_txt: &str) -> Result<Landscape> {
+        let mut l = Landscape::default();
+        let ini = IniFile::parse(scenario_txt);
+        let section = match ini.section("Landscape") {
+            Some(section) => section,
+            None => return Ok(l),
+        };
+        for (key, value) in &section.entries {
+            let v = value.as_str();
+            match key.as_str() {
+                "MapWidth"          => l.map_width = ScenarioValue::parse(v, l.map_width)?,
+                "MapHeight"         => l.map_height = ScenarioValue::parse(v, l.map_height)?,
+                "MapZoom"           => l.map_zoom = ScenarioValue::parse(v, l.map_zoom)?,
+                "MapPlayerExtend"   => l.map_player_extend = parse_int(key, v)? != 0,
+                "ExactLandscape"    => l.exact_landscape = parse_int(key, v)? != 0,
+                "BottomOpen"        => l.bottom_open = parse_int(key, v)? != 0,
+                "TopOpen"           => l.top_open = parse_int(key, v)? != 0,
+                "LeftOpen"          => l.left_open = parse_int(key, v)?,
+                "RightOpen"         => l.right_open = parse_int(key, v)?,
+                "AutoScanSideOpen"  => l.auto_scan_side_open = parse_int(key, v)? != 0,
+                "Sky"               => l.sky = Some(v.to_owned()),
+                "SkyFade"           => {
+                    for (i, part) in v.split(',').take(6).enumerate() {
+                        l.sky_fade[i] = parse_int(key, part)?;
+                    }
+                },
+                "Amplitude"         => l.amplitude = ScenarioValue::parse(v, l.amplitude)?,
+                "Phase"             => l.phase = ScenarioValue::parse(v, l.phase)?,
+                "Period"            => l.period = ScenarioValue::parse(v, l.period)?,
+                "Random"            => l.random = ScenarioValue::parse(v, l.random)?,
+                "LiquidLevel"       => l.liquid_level = ScenarioValue::parse(v, l.liquid_level)?,
+                "Gravity"           => l.gravity = ScenarioValue::parse(v, l.gravity)?,
+                "Material"          => l.material = v.to_owned(),
+                "Liquid"            => l.liquid = v.to_owned(),
+                "Layers"            => l.layers = parse_name_list(key, v)?,
+                _ => () // not relevant for the map
+            }
+        }
+        Ok(l)
+    }
+}
+
+fn parse_int(key: &str, value: &str) -> Result<i32> {
+    value.trim().parse()
+        .chain_err(|| format!("invalid {} '{}'", key, value))
+}
+
+/// Parses a list of `Name=Count` pairs like `Rock=10;Gold=2`.
+pub(crate) fn parse_name_list(key: &str, value: &str) -> Result<Vec<(String, i32)>> {
+    value.split([';', ','])
+        .map(|item| item.trim())
+        .filter(|item| !item.is_empty())
+        .map(|item| match item.find('=') {
+            Some(pos) => Ok((item[..pos].trim().to_owned(), parse_int(key, &item[pos + 1..])?)),
+            None => Ok((item.to_owned(), 0)),
+        })
+        .collect()
+}
+
+/// A scenario (.ocs folder or packed group) with everything needed to render its map.
+pub struct Scenario {
+    path: PathBuf,
+    landscape: Landscape,
+    map_type: MapType,
+    map_source: String,
+    script: Option<String>,
+    scenpar: Option<Scenpar>,
+}
+
+impl Scenario {
+    /// Loads Scenario.txt, the map source (Map.c, or Landscape.txt if there is no Map.c),
+    /// Script.c and ParameterDefs.txt from a scenario.
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Scenario> {
+        let path = path.as_ref().to_owned();
+        let mut group = Group::open(path.to_str().unwrap(), false)
+            .chain_err(|| format!("couldn't open scenario {}", path.display()))?;
+
+        let landscape = match group.load_entry("Scenario.txt") {
+            Ok(data) => Landscape::parse(&String::from_utf8_lossy(&data))
+                .chain_err(|| "invalid [Landscape] section in Scenario.txt")?,
+            Err(_) => Landscape::default(),
+        };
+
+        let (map_type, data) = if let Ok(data) = group.load_entry("Map.c") {
+            (MapType::MapC, data)
+        } else if let Ok(data) = group.load_entry("Landscape.txt") {
+            (MapType::LandscapeTxt, data)
+        } else {
+            bail!("scenario {} has neither Map.c nor Landscape.txt", path.display());
+        };
+
+        // Read here as packed scenarios have no Script.c on disk for the engine to load.
+        let script = if group.find("Script.c")?.is_empty() {
+            None
+        } else {
+            Some(String::from_utf8_lossy(&group.load_entry("Script.c")?).into_owned())
+        };
+
+        let scenpar = if group.load_entry("ParameterDefs.txt").is_ok() {
+            let mut scenpar = Scenpar::new();
+            scenpar.load(&group)?;
+            Some(scenpar)
+        } else {
+            None
+        };
+
+        Ok(Scenario {
+            path,
+            landscape,
+            map_type,
+            map_source: String::from_utf8_lossy(&data).into_owned(),
+            script,
+            scenpar,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn landscape(&self) -> &Landscape {
+        &self.landscape
+    }
+
+    pub fn map_type(&self) -> MapType {
+        self.map_type
+    }
+
+    /// Returns the path of the map source file, e.g. for error messages.
+    pub fn map_filename(&self) -> PathBuf {
+        match self.map_type {
+            MapType::MapC => self.path.join("Map.c"),
+            MapType::LandscapeTxt => self.path.join("Landscape.txt"),
+        }
+    }
+
+    pub fn map_source(&self) -> &str {
+        &self.map_source
+    }
+
+    /// Returns the path of the scenario script, e.g. for error messages.
+    pub fn script_path(&self) -> PathBuf {
+        self.path.join("Script.c")
+    }
+
+    /// Returns the scenario script, used by Landscape.txt algo=script overlays.
+    pub fn script(&self) -> Option<&str> {
+        self.script.as_deref()
+    }
+
+    pub fn scenpar(&self) -> Option<&Scenpar> {
+        self.scenpar.as_ref()
+    }
+
+    pub fn scenpar_mut(&mut self) -> Option<&mut Scenpar> {
+        self.scenpar.as_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Landscape, ScenarioValue};
+
+    #[test]
+    fn value() {
+        assert_eq!("100".parse::<ScenarioValue>().unwrap(), ScenarioValue::new(100, 0, 1, i32::MAX));
+        assert_eq!(" 100, 10 ,64,250".parse::<ScenarioValue>().unwrap(), ScenarioValue::new(100, 10, 64, 250));
+        assert!("1,2,3,4,5".parse::<ScenarioValue>().is_err());
+        assert!("wide".parse::<ScenarioValue>().is_err());
+    }
+
+    #[test]
+    fn parse() {
+        let defaults = Landscape::parse("[Head]\nTitle=Test\n").unwrap();
+        assert_eq!(defaults.map_width, ScenarioValue::new(100, 0, 64, 250));
+        assert_eq!(defaults.map_zoom, ScenarioValue::new(8, 0, 5, 15));
+        assert!(defaults.top_open && !defaults.map_player_extend);
+        assert!(defaults.layers.is_empty());
+
+        let l = Landscape::parse("[Landscape]\nMapWidth=120,20\nMapHeight=80,0,60,90\nMapZoom=10\n\
+                                  MapPlayerExtend=1\nTopOpen=0\nLeftOpen=30\nSkyFade=1,2,3,4,5,6\n\
+                                  Layers=Rock=10;Gold=2,Coal\n\n[Game]\nMapWidth=1\n").unwrap();
+        // Omitted components keep their defaults.
+        assert_eq!(l.map_width, ScenarioValue::new(120, 20, 64, 250));
+        assert_eq!(l.map_height, ScenarioValue::new(80, 0, 60, 90));
+        assert_eq!(l.map_zoom, ScenarioValue::new(10, 0, 5, 15));
+        assert!(l.map_player_extend && !l.top_open);
+        assert_eq!(l.left_open, 30);
+        assert_eq!(l.sky_fade, [1, 2, 3, 4, 5, 6]);
+        assert_eq!(l.layers, vec![("Rock".to_owned(), 10), ("Gold".to_owned(), 2), ("Coal".to_owned(), 0)]);
+
+        assert!(Landscape::parse("[Landscape]\nMapWidth=wide\n").is_err());
+    }
+}
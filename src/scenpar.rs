@@ -4,12 +4,50 @@ use crate::Handle;
 use crate::group::Group;
 
 use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
 use error_chain::bail;
 
 pub struct Scenpar {
     handle: *mut C4ScenparHandle,
 }
 
+/// Definition of a scenario parameter from ParameterDefs.txt.
+#[derive(Clone, Debug)]
+pub struct ScenparDef {
+    /// ID used in scripts as `SCENPAR_<ID>`.
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub param_type: ScenparType,
+    pub default: i32,
+    /// Whether this parameter is an achievement instead of a setting.
+    pub achievement: bool,
+    pub options: Vec<ScenparOption>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScenparType {
+    /// A choice from a list of options.
+    Enum,
+    /// A type added to the engine after this library was written.
+    Unknown(i32),
+}
+
+/// A selectable value of a scenario parameter.
+#[derive(Clone, Debug)]
+pub struct ScenparOption {
+    pub value: i32,
+    pub name: String,
+    pub description: String,
+}
+
+impl ScenparDef {
+    /// Returns the option with the given value.
+    pub fn option(&self, value: i32) -> Option<&ScenparOption> {
+        self.options.iter().find(|o| o.value == value)
+    }
+}
+
 impl Scenpar {
     pub fn new() -> Scenpar {
         unsafe {
@@ -40,6 +78,65 @@ impl Scenpar {
         }
     }
 
+    /// Returns the definitions of all parameters in ParameterDefs.txt order.
+    pub fn defs(&self) -> Vec<ScenparDef> {
+        unsafe {
+            let num = c4_scenpar_handle_get_num_defs(self.handle);
+            (0..num).map(|i| self.def(i)).collect()
+        }
+    }
+
+    /// Returns the definition of the parameter with the given ID.
+    pub fn def_by_id(&self, id: &str) -> Option<ScenparDef> {
+        self.defs().into_iter().find(|d| d.id == id)
+    }
+
+    unsafe fn def(&self, index: usize) -> ScenparDef {
+        let h = self.handle;
+        let num_options = c4_scenpar_handle_get_def_num_options(h, index);
+        ScenparDef {
+            id: to_string(c4_scenpar_handle_get_def_id(h, index)),
+            name: to_string(c4_scenpar_handle_get_def_name(h, index)),
+            description: to_string(c4_scenpar_handle_get_def_description(h, index)),
+            param_type: match c4_scenpar_handle_get_def_type(h, index) {
+                0 => ScenparType::Enum,
+                t => ScenparType::Unknown(t),
+            },
+            default: c4_scenpar_handle_get_def_default(h, index),
+            achievement: c4_scenpar_handle_get_def_is_achievement(h, index),
+            options: (0..num_options).map(|o| ScenparOption {
+                value: c4_scenpar_handle_get_def_option_value(h, index, o),
+                name: to_string(c4_scenpar_handle_get_def_option_name(h, index, o)),
+                description: to_string(c4_scenpar_handle_get_def_option_description(h, index, o)),
+            }).collect(),
+        }
+    }
+
+    /// Returns the current value of a parameter, or its default if it wasn't set.
+    pub fn value(&self, def: &ScenparDef) -> i32 {
+        self.get_value_by_id(&def.id, def.default)
+    }
+
+    /// Sets a parameter after checking that both ID and value are defined in ParameterDefs.txt.
+    pub fn select(&mut self, id: &str, value: i32) -> Result<()> {
+        let defs = self.defs();
+        let def = match defs.iter().find(|d| d.id == id) {
+            Some(def) => def,
+            None => {
+                let ids: Vec<&str> = defs.iter().map(|d| d.id.as_str()).collect();
+                bail!("unknown scenario parameter '{}' (valid: {})", id, ids.join(", "));
+            }
+        };
+        if def.option(value).is_none() {
+            let options: Vec<String> = def.options.iter()
+                .map(|o| format!("{} ({})", o.value, o.name))
+                .collect();
+            bail!("invalid value {} for scenario parameter {} (valid: {})", value, id, options.join(", "));
+        }
+        self.set_value(id, value, false);
+        Ok(())
+    }
+
     pub fn set_value(&mut self, id: &str, value: i32, only_if_larger: bool) {
         unsafe {
             c4_scenpar_handle_set_value(self.handle,
@@ -49,6 +146,14 @@ impl Scenpar {
     }
 }
 
+unsafe fn to_string(s: *const c_char) -> String {
+    if s.is_null() {
+        String::new()
+    } else {
+        CStr::from_ptr(s).to_string_lossy().into_owned()
+    }
+}
+
 impl Drop for Scenpar {
     fn drop(&mut self) {
         unsafe {
@@ -0,0 +1,294 @@
+use image::{imageops, GenericImage, Rgb, RgbImage};
+
+const GLYPH_WIDTH: u32 = 5;
+const GLYPH_HEIGHT: u32 = 7;
+/// Space between tiles, labels and the image border.
+const PADDING: u32 = 4;
+const LINE_HEIGHT: u32 = GLYPH_HEIGHT + 2;
+/// Placeholder size if there are no images at all.
+const DEFAULT_SIZE: u32 = 64;
+
+/// Tiles images into a grid with a text label below each one.
+///
+/// Tiles can have different sizes, the grid cells are as large as the largest tile or label.
+pub struct ContactSheet {
+    /// Images with their label, `None` for placeholders.
+    tiles: Vec<(Option<RgbImage>, String)>,
+    columns: Option<u32>,
+    scale: u32,
+    background: Rgb<u8>,
+    foreground: Rgb<u8>,
+}
+
+/// Position of a tile on the contact sheet.
+#[derive(Clone, Copy, Debug)]
+pub struct TileRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl ContactSheet {
+    pub fn new() -> ContactSheet {
+        ContactSheet {
+            tiles: Vec::new(),
+            columns: None,
+            scale: 1,
+            background: Rgb([40, 40, 40]),
+            foreground: Rgb([255, 255, 255]),
+        }
+    }
+
+    /// Sets the number of columns. Per default, the grid is about square.
+    pub fn columns(&mut self, columns: u32) -> &mut ContactSheet {
+        self.columns = Some(columns.max(1));
+        self
+    }
+
+    /// Scales up all images by an integer factor, keeping the pixels sharp.
+    pub fn scale(&mut self, scale: u32) -> &mut ContactSheet {
+        self.scale = scale.max(1);
+        self
+    }
+
+    /// Sets the background and label colors.
+    pub fn colors(&mut self, background: Rgb<u8>, foreground: Rgb<u8>) -> &mut ContactSheet {
+        self.background = background;
+        self.foreground = foreground;
+        self
+    }
+
+    /// Adds an image with a label below it.
+    ///
+    /// Labels may have several lines separated by '\n'. Only ASCII letters, digits and common
+    /// punctuation are drawn, letters in upper case.
+    pub fn add(&mut self, image: RgbImage, label: &str) -> &mut ContactSheet {
+        self.tiles.push((Some(image), label.to_owned()));
+        self
+    }
+
+    /// Adds a red tile as large as the largest image, e.g. for a map that failed to render.
+    pub fn add_placeholder(&mut self, label: &str) -> &mut ContactSheet {
+        self.tiles.push((None, label.to_owned()));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+
+    /// Returns where the image of the tile with the given index ends up on the sheet.
+    pub fn tile_rect(&self, index: usize) -> TileRect {
+        let layout = self.layout();
+        let (x, y) = layout.cell_origin(index);
+        let (width, height) = match self.tiles[index] {
+            (Some(ref image), _) => (image.width() * self.scale, image.height() * self.scale),
+            (None, _) => (layout.image_width, layout.image_height),
+        };
+        TileRect { x, y, width, height }
+    }
+
+    /// Draws the contact sheet.
+    pub fn render(&self) -> RgbImage {
+        let layout = self.layout();
+        let rows = (self.tiles.len() as u32).div_ceil(layout.columns);
+        let width = PADDING + layout.columns * (layout.cell_width + PADDING);
+        let height = PADDING + rows * (layout.cell_height + PADDING);
+        let mut sheet = RgbImage::from_pixel(width, height, self.background);
+        for (i, (image, label)) in self.tiles.iter().enumerate() {
+            let (x, y) = layout.cell_origin(i);
+            match image {
+                Some(image) if self.scale > 1 => {
+                    let scaled = imageops::resize(image, image.width() * self.scale, image.height() * self.scale, imageops::FilterType::Nearest);
+                    sheet.copy_from(&scaled, x, y)
+                },
+                Some(image) => sheet.copy_from(image, x, y),
+                None => sheet.copy_from(&RgbImage::from_pixel(layout.image_width, layout.image_height, Rgb([128, 0, 0])), x, y),
+            }.expect("tile doesn't fit into its cell");
+            for (line_nr, line) in label.lines().enumerate() {
+                let line_y = y + layout.image_height + PADDING + line_nr as u32 * LINE_HEIGHT;
+                draw_text(&mut sheet, x, line_y, line, self.foreground);
+            }
+        }
+        sheet
+    }
+
+    fn layout(&self) -> Layout {
+        let columns = self.columns
+            .unwrap_or_else(|| (self.tiles.len() as f64).sqrt().ceil() as u32)
+            .max(1);
+        let images = self.tiles.iter().filter_map(|(img, _)| img.as_ref());
+        let image_width = images.clone().map(|img| img.width() * self.scale).max().unwrap_or(DEFAULT_SIZE);
+        let image_height = images.map(|img| img.height() * self.scale).max().unwrap_or(DEFAULT_SIZE);
+        let label_width = self.tiles.iter()
+            .flat_map(|(_, label)| label.lines())
+            .map(text_width)
+            .max().unwrap_or(0);
+        let label_lines = self.tiles.iter().map(|(_, label)| label.lines().count() as u32).max().unwrap_or(0);
+        let label_height = if label_lines > 0 { PADDING + label_lines * LINE_HEIGHT } else { 0 };
+        Layout {
+            columns,
+            cell_width: image_width.max(label_width),
+            cell_height: image_height + label_height,
+            image_width,
+            image_height,
+        }
+    }
+}
+
+impl Default for ContactSheet {
+    fn default() -> ContactSheet {
+        ContactSheet::new()
+    }
+}
+
+struct Layout {
+    columns: u32,
+    cell_width: u32,
+    cell_height: u32,
+    image_width: u32,
+    image_height: u32,
+}
+
+impl Layout {
+    fn cell_origin(&self, index: usize) -> (u32, u32) {
+        let column = index as u32 % self.columns;
+        let row = index as u32 / self.columns;
+        (PADDING + column * (self.cell_width + PADDING),
+         PADDING + row * (self.cell_height + PADDING))
+    }
+}
+
+fn text_width(text: &str) -> u32 {
+    match text.chars().count() as u32 {
+        0 => 0,
+        n => n * (GLYPH_WIDTH + 1) - 1,
+    }
+}
+
+/// Draws a single line of text with the built-in 5x7 font.
+fn draw_text(img: &mut RgbImage, x: u32, y: u32, text: &str, color: Rgb<u8>) {
+    for (i, c) in text.chars().enumerate() {
+        let glyph_x = x + i as u32 * (GLYPH_WIDTH + 1);
+        let glyph = glyph(c);
+        for (row, bits) in glyph.iter().enumerate() {
+            for col in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - col)) != 0 {
+                    let (px, py) = (glyph_x + col, y + row as u32);
+                    if px < img.width() && py < img.height() {
+                        img.put_pixel(px, py, color);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Returns the 5x7 bitmap of a character, one byte per row with the leftmost pixel in bit 4.
+fn glyph(c: char) -> [u8; 7] {
+    match c.to_ascii_uppercase() {
+        ' ' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00],
+        '0' => [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E],
+        '1' => [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        '2' => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F],
+        '3' => [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E],
+        '4' => [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02],
+        '5' => [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E],
+        '6' => [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E],
+        '7' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08],
+        '8' => [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E],
+        '9' => [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C],
+        'A' => [0x0E, 0x11, 0x11, 0x11, 0x1F, 0x11, 0x11],
+        'B' => [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E],
+        'C' => [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E],
+        'D' => [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C],
+        'E' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F],
+        'F' => [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10],
+        'G' => [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F],
+        'H' => [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11],
+        'I' => [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E],
+        'J' => [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C],
+        'K' => [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11],
+        'L' => [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F],
+        'M' => [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11],
+        'N' => [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11],
+        'O' => [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'P' => [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10],
+        'Q' => [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D],
+        'R' => [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11],
+        'S' => [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E],
+        'T' => [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04],
+        'U' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E],
+        'V' => [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04],
+        'W' => [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A],
+        'X' => [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11],
+        'Y' => [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04],
+        'Z' => [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F],
+        '=' => [0x00, 0x00, 0x1F, 0x00, 0x1F, 0x00, 0x00],
+        ',' => [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08],
+        '.' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C],
+        ':' => [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00],
+        '-' => [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00],
+        '_' => [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F],
+        '+' => [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00],
+        '/' => [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00],
+        '(' => [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02],
+        ')' => [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08],
+        '#' => [0x0A, 0x0A, 0x1F, 0x0A, 0x1F, 0x0A, 0x0A],
+        '%' => [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03],
+        _   => [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04], // '?'
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ContactSheet, LINE_HEIGHT, PADDING};
+
+    use image::{Rgb, RgbImage};
+
+    #[test]
+    fn grid() {
+        let mut sheet = ContactSheet::new();
+        for i in 0..5 {
+            sheet.add(RgbImage::new(10, 8), &i.to_string());
+        }
+        // About square, so three columns and two rows for five tiles.
+        let cell_height = 8 + PADDING + LINE_HEIGHT;
+        let rect = sheet.tile_rect(4);
+        assert_eq!((rect.x, rect.y, rect.width, rect.height), (2 * PADDING + 10, 2 * PADDING + cell_height, 10, 8));
+        let img = sheet.render();
+        assert_eq!(img.dimensions(), (PADDING + 3 * (10 + PADDING), PADDING + 2 * (cell_height + PADDING)));
+
+        sheet.columns(5);
+        assert_eq!(sheet.tile_rect(4).x, PADDING + 4 * (10 + PADDING));
+        assert_eq!(sheet.tile_rect(4).y, PADDING);
+    }
+
+    #[test]
+    fn oversized_tiles() {
+        let mut sheet = ContactSheet::new();
+        sheet.scale(2).columns(2)
+             .add(RgbImage::new(10, 8), "small")
+             .add(RgbImage::from_pixel(30, 20, Rgb([0, 255, 0])), "large")
+             .add_placeholder("a label wider than the images");
+        // Cells fit the largest tile and the longest label, smaller tiles keep their size.
+        let label_width = 29 * 6 - 1;
+        let small = sheet.tile_rect(0);
+        assert_eq!((small.width, small.height), (20, 16));
+        let large = sheet.tile_rect(1);
+        assert_eq!((large.x, large.width, large.height), (2 * PADDING + label_width, 60, 40));
+        let placeholder = sheet.tile_rect(2);
+        assert_eq!((placeholder.x, placeholder.y), (PADDING, 2 * PADDING + 40 + PADDING + LINE_HEIGHT));
+        assert_eq!((placeholder.width, placeholder.height), (60, 40));
+
+        let img = sheet.render();
+        assert_eq!(*img.get_pixel(large.x + 59, large.y + 39), Rgb([0, 255, 0]));
+        assert_eq!(*img.get_pixel(placeholder.x + 59, placeholder.y + 39), Rgb([128, 0, 0]));
+        assert_eq!(*img.get_pixel(small.x + 20, small.y), Rgb([40, 40, 40]));
+    }
+}